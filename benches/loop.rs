@@ -0,0 +1,31 @@
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+// Runs programs/bench_loop.txt (10^6 iterations) with both engines and reports the best of a
+// few runs. Run with `cargo bench`. Set MINIPL_BASELINE to the binary of another build, for
+// example one checked out from an older commit, to time its tree engine next to this one:
+//   MINIPL_BASELINE=/path/to/old/minipl-interpreter cargo bench
+const RUNS: usize = 5;
+
+fn main() {
+    let program = concat!(env!("CARGO_MANIFEST_DIR"), "/programs/bench_loop.txt");
+    let current = env!("CARGO_BIN_EXE_minipl-interpreter").to_string();
+    let mut runs = vec![("tree", current.clone(), "tree"), ("vm", current, "vm")];
+    if let Ok(baseline) = std::env::var("MINIPL_BASELINE") {
+        runs.insert(0, ("baseline tree", baseline, "tree"));
+    }
+    for (name, binary, engine) in runs {
+        let mut best = Duration::MAX;
+        for _ in 0..RUNS {
+            let start = Instant::now();
+            let output = Command::new(&binary)
+                .args(["--engine", engine, program])
+                .output()
+                .expect("failed to run the interpreter");
+            let elapsed = start.elapsed();
+            assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+            best = best.min(elapsed);
+        }
+        println!("{:<13} {:>8.1} ms", name, best.as_secs_f64() * 1000.0);
+    }
+}
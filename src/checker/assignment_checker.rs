@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+
+use crate::data_structures::tree::ArenaTree;
+use crate::language::ast::{AstItem, VariableType};
+
+// Strict mode: reports reads of scalar variables that may still hold their type default because
+// no assignment or read reached them on every path. Arrays are exempt, their elements are not
+// tracked. A for loop body may run zero times, so assignments in it do not count after the loop.
+pub struct AssignmentChecker<'a> {
+    ast: &'a ArenaTree<AstItem>,
+}
+
+impl<'a> AssignmentChecker<'a> {
+
+    pub fn new(ast: &'a ArenaTree<AstItem>) -> Self {
+        Self { ast }
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        let mut unassigned = HashSet::new();
+        self.check_stmts(&self.ast.arena[0].children, &mut unassigned, true)
+    }
+
+    // unassigned holds the variables that may be unassigned before the statements and is
+    // updated to the state after them. Errors are only reported when report is set.
+    fn check_stmts(&self, stmts: &[usize], unassigned: &mut HashSet<String>, report: bool) -> Result<(), String> {
+        for stmt in stmts {
+            self.check_stmt(*stmt, unassigned, report)?;
+        }
+        Ok(())
+    }
+
+    fn check_stmt(&self, index: usize, unassigned: &mut HashSet<String>, report: bool) -> Result<(), String> {
+        let node = &self.ast.arena[index];
+        match &node.val {
+            AstItem::Assign(_) | AstItem::Read(_) => {
+                if let Some(expr) = node.children.get(1) {
+                    self.check_expr(*expr, unassigned, report)?;
+                }
+                let target = &self.ast.arena[node.children[0]];
+                match &target.val {
+                    AstItem::Variable(t) => {
+                        let declaration_only = matches!(node.val, AstItem::Assign(_)) && node.children.len() == 1;
+                        if declaration_only && !matches!(t.var_type, VariableType::Array(_, _)) {
+                            unassigned.insert(t.name.clone());
+                        } else {
+                            unassigned.remove(&t.name);
+                        }
+                    }
+                    AstItem::Index(_) => self.check_expr(target.children[1], unassigned, report)?,
+                    t => panic!("Unexpected assignment target {:#?}", t)
+                }
+            }
+            AstItem::Print(_) | AstItem::Println(_) | AstItem::Assert(_) => {
+                for child in &node.children {
+                    self.check_expr(*child, unassigned, report)?;
+                }
+            }
+            AstItem::For(_) => {
+                for range_child in &self.ast.arena[node.children[1]].children {
+                    self.check_expr(*range_child, unassigned, report)?;
+                }
+                // The control variable is assigned before every iteration and after the last one
+                let control = match &self.ast.arena[node.children[0]].val {
+                    AstItem::Variable(t) => &t.name,
+                    t => panic!("ERROR for statement expected variable, found {:#?}", t)
+                };
+                unassigned.remove(control);
+                // Later iterations start from what the previous one left behind, so grow the
+                // entry state until it is stable before reporting anything in the body
+                let body = &self.ast.arena[node.children[2]].children;
+                let mut entry = unassigned.clone();
+                loop {
+                    let mut exit = entry.clone();
+                    self.check_stmts(body, &mut exit, false)?;
+                    exit.remove(control);
+                    if exit.is_subset(&entry) {
+                        break;
+                    }
+                    entry.extend(exit);
+                }
+                let mut exit = entry.clone();
+                self.check_stmts(body, &mut exit, report)?;
+                unassigned.extend(exit);
+                unassigned.remove(control);
+            }
+            t => panic!("Unexpected statement node {:#?}", t)
+        }
+        Ok(())
+    }
+
+    fn check_expr(&self, index: usize, unassigned: &HashSet<String>, report: bool) -> Result<(), String> {
+        let node = &self.ast.arena[index];
+        if let AstItem::Variable(t) = &node.val {
+            if report && unassigned.contains(&t.name) {
+                return Err(format!("ERROR variable {} may be read before it is assigned, {}", t.name, t.source_info));
+            }
+        }
+        for child in &node.children {
+            self.check_expr(*child, unassigned, report)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,2 @@
+pub mod type_checker;
+pub mod assignment_checker;
\ No newline at end of file
@@ -0,0 +1,221 @@
+use crate::data_structures::tree::ArenaTree;
+use crate::language::ast::{AstItem, BinOpType, SourceInfo, VariableType};
+use crate::language::builtins::{builtin_signature, builtin_variadic};
+use crate::language::format::{fields, parse_format};
+use crate::interpreter::natives::NativeRegistry;
+
+pub struct TypeChecker<'a> {
+    ast: &'a ArenaTree<AstItem>,
+    natives: Option<&'a NativeRegistry>
+}
+
+impl<'a> TypeChecker<'a> {
+
+    pub fn new(ast: &'a ArenaTree<AstItem>) -> Self {
+        Self { ast, natives: None }
+    }
+
+    // Calls may also use the functions registered by the host
+    pub fn with_natives(mut self, natives: &'a NativeRegistry) -> Self {
+        self.natives = Some(natives);
+        self
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        for child in &self.ast.arena[0].children {
+            self.check_stmt(*child)?;
+        }
+        Ok(())
+    }
+
+    // Type of a lone expression parsed by SyntaxParser::parse_expression
+    pub fn check_expression(&self) -> Result<VariableType, String> {
+        self.check_expr(self.ast.arena[0].children[0])
+    }
+
+    fn check_stmt(&self, index: usize) -> Result<(), String> {
+        let node = &self.ast.arena[index];
+        match &node.val {
+            AstItem::Assign(_) => {
+                self.check_target(node.children[0])?;
+                let target_type = self.check_expr(node.children[0])?;
+                if node.children.len() > 1 {
+                    if let VariableType::Array(_, _) = target_type {
+                        return Err(format!("ERROR arrays cannot be assigned as a whole, {}", self.location(index)));
+                    }
+                    let expr_type = self.check_expr(node.children[1])?;
+                    if expr_type != target_type {
+                        return Err(format!("ERROR cannot assign {} to {}, {}", expr_type, target_type, self.location(index)));
+                    }
+                }
+            }
+            AstItem::Read(_) => {
+                self.check_target(node.children[0])?;
+                match self.check_expr(node.children[0])? {
+                    VariableType::Int | VariableType::String | VariableType::Bool => (),
+                    t => return Err(format!("ERROR cannot read a value of type {}, {}", t, self.location(index)))
+                }
+            }
+            AstItem::Print(_) | AstItem::Println(_) => {
+                for child in &node.children {
+                    if let t @ VariableType::Array(_, _) = self.check_expr(*child)? {
+                        return Err(format!("ERROR cannot print a value of type {}, {}", t, self.location(*child)));
+                    }
+                }
+            }
+            AstItem::Assert(_) => {
+                let t = self.check_expr(node.children[0])?;
+                if t != VariableType::Bool {
+                    return Err(format!("ERROR assert expects bool, found {}, {}", t, self.location(index)));
+                }
+                if let Some(message) = node.children.get(1) {
+                    let t = self.check_expr(*message)?;
+                    if t != VariableType::String {
+                        return Err(format!("ERROR assert message must be a string, found {}, {}", t, self.location(*message)));
+                    }
+                }
+            }
+            AstItem::For(_) => {
+                let t = self.check_expr(node.children[0])?;
+                if t != VariableType::Int {
+                    return Err(format!("ERROR loop variable must be int, found {}, {}", t, self.location(index)));
+                }
+                for range_child in &self.ast.arena[node.children[1]].children {
+                    let t = self.check_expr(*range_child)?;
+                    if t != VariableType::Int {
+                        return Err(format!("ERROR range values must be int, found {}, {}", t, self.location(*range_child)));
+                    }
+                }
+                for child in &self.ast.arena[node.children[2]].children {
+                    self.check_stmt(*child)?;
+                }
+            }
+            t => panic!("Unexpected statement node {:#?}", t)
+        }
+        Ok(())
+    }
+
+    fn check_expr(&self, index: usize) -> Result<VariableType, String> {
+        let node = &self.ast.arena[index];
+        match &node.val {
+            AstItem::Constant(t) => Ok(t.const_type.clone()),
+            AstItem::Variable(t) => Ok(t.var_type.clone()),
+            AstItem::Index(t) => {
+                let array_type = self.check_expr(node.children[0])?;
+                let index_type = self.check_expr(node.children[1])?;
+                if index_type != VariableType::Int {
+                    return Err(format!("ERROR array index must be int, found {}, {}", index_type, t));
+                }
+                match array_type {
+                    VariableType::Array(element_type, _) => Ok(*element_type),
+                    VariableType::String => Ok(VariableType::String),
+                    _ => Err(format!("ERROR cannot index a value of type {}, {}", array_type, t))
+                }
+            }
+            AstItem::Not(_) => {
+                match self.check_expr(node.children[0])? {
+                    VariableType::Bool => Ok(VariableType::Bool),
+                    t => Err(format!("ERROR logical not expects bool, found {}, {}", t, self.location(index)))
+                }
+            }
+            AstItem::Negate(_) => {
+                match self.check_expr(node.children[0])? {
+                    VariableType::Int => Ok(VariableType::Int),
+                    t => Err(format!("ERROR negation expects int, found {}, {}", t, self.location(index)))
+                }
+            }
+            AstItem::BinOp(op, _) => {
+                let left = self.check_expr(node.children[0])?;
+                let right = self.check_expr(node.children[1])?;
+                let result = match (op, &left, &right) {
+                    (BinOpType::Plus, VariableType::Int, VariableType::Int) => Some(VariableType::Int),
+                    (BinOpType::Plus, VariableType::String, VariableType::String) => Some(VariableType::String),
+                    (BinOpType::Minus | BinOpType::Multiply | BinOpType::Divide, VariableType::Int, VariableType::Int) => Some(VariableType::Int),
+                    (BinOpType::LessThan | BinOpType::GreaterThan | BinOpType::LessEqual | BinOpType::GreaterEqual | BinOpType::Equal | BinOpType::NotEqual, VariableType::Array(_, _), _) => None,
+                    (BinOpType::LessThan | BinOpType::GreaterThan | BinOpType::LessEqual | BinOpType::GreaterEqual | BinOpType::Equal | BinOpType::NotEqual, _, _) if left == right => Some(VariableType::Bool),
+                    (BinOpType::And | BinOpType::Or, VariableType::Bool, VariableType::Bool) => Some(VariableType::Bool),
+                    _ => None
+                };
+                match result {
+                    Some(t) => Ok(t),
+                    None => Err(format!("ERROR operator {} cannot be applied to {} and {}, {}", op, left, right, self.location(index)))
+                }
+            }
+            AstItem::Call(t) => {
+                let signature = builtin_signature(&t.name).or_else(|| self.natives.and_then(|natives| natives.signature(&t.name)));
+                let (parameters, return_type) = match signature {
+                    Some(signature) => signature,
+                    None => return Err(format!("ERROR unknown function {}, {}", t.name, t.source_info))
+                };
+                let variadic = builtin_variadic(&t.name);
+                if variadic && node.children.len() < parameters.len() {
+                    return Err(format!("ERROR function {} expects at least {} arguments, found {}, {}", t.name, parameters.len(), node.children.len(), t.source_info));
+                }
+                if !variadic && parameters.len() != node.children.len() {
+                    return Err(format!("ERROR function {} expects {} arguments, found {}, {}", t.name, parameters.len(), node.children.len(), t.source_info));
+                }
+                for (parameter, child) in parameters.iter().zip(&node.children) {
+                    let arg_type = self.check_expr(*child)?;
+                    if &arg_type != parameter {
+                        return Err(format!("ERROR function {} expects {} argument, found {}, {}", t.name, parameter, arg_type, self.location(*child)));
+                    }
+                }
+                let mut values = Vec::new();
+                for child in &node.children[parameters.len()..] {
+                    match self.check_expr(*child)? {
+                        arg_type @ VariableType::Array(_, _) => return Err(format!("ERROR function {} cannot take a value of type {}, {}", t.name, arg_type, self.location(*child))),
+                        arg_type => values.push((arg_type, *child))
+                    }
+                }
+                if t.name == "format" {
+                    self.check_format(node.children[0], &values)?;
+                }
+                Ok(return_type)
+            }
+            t => panic!("Unexpected expression node {:#?}", t)
+        }
+    }
+
+    // A constant format string is checked against the values here, any other one when it is used
+    fn check_format(&self, format: usize, values: &[(VariableType, usize)]) -> Result<(), String> {
+        let format_text = match &self.ast.arena[format].val {
+            AstItem::Constant(t) => &t.value,
+            _ => return Ok(())
+        };
+        let parts = match parse_format(format_text) {
+            Ok(t) => t,
+            Err(e) => return Err(format!("ERROR invalid format string: {}, {}", e, self.location(format)))
+        };
+        let fields = fields(&parts);
+        if fields.len() != values.len() {
+            return Err(format!("ERROR format string expects {} values, found {}, {}", fields.len(), values.len(), self.location(format)));
+        }
+        for (number, (field, (value_type, child))) in fields.iter().zip(values).enumerate() {
+            if &field.value_type != value_type {
+                return Err(format!("ERROR format field {} expects {}, found {}, {}", number + 1, field.value_type, value_type, self.location(*child)));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_target(&self, index: usize) -> Result<(), String> { // Strings are immutable, only array elements can be assigned by index
+        let node = &self.ast.arena[index];
+        if let AstItem::Index(t) = &node.val {
+            if let VariableType::String = self.check_expr(node.children[0])? {
+                return Err(format!("ERROR cannot assign to a character of a string, {}", t));
+            }
+        }
+        Ok(())
+    }
+
+    fn location(&self, index: usize) -> String {
+        match self.source_info(index) {
+            Some(t) => t.to_string(),
+            None => "unknown location".to_string()
+        }
+    }
+
+    fn source_info(&self, index: usize) -> Option<&SourceInfo> {
+        self.ast.arena[index].val.source_info()
+    }
+}
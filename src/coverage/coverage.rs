@@ -0,0 +1,229 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::rc::Rc;
+
+use crate::data_structures::tree::ArenaTree;
+use crate::interpreter::hooks::{ExecutionHook, StatementContext};
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::language::ast::{AstItem, SourceInfo};
+
+// Hit counts of one source file, keyed by line and by (line, block, branch) as in lcov
+#[derive(Default)]
+struct FileCoverage {
+    lines: BTreeMap<i32, u64>,
+    branches: BTreeMap<(i32, usize, usize), Option<u64>>
+}
+
+struct ActiveStatement {
+    index: usize,
+    entered: bool
+}
+
+// Counts shared between the hook given to the interpreter and the handle that saves them
+#[derive(Default)]
+struct Counts {
+    statements: Vec<(usize, SourceInfo)>,
+    loops: Vec<(usize, SourceInfo)>,
+    hits: HashMap<usize, u64>,
+    entered: HashMap<usize, u64>,
+    skipped: HashMap<usize, u64>,
+    active: Vec<ActiveStatement>
+}
+
+// Records which statements ran and whether loop bodies were entered or skipped. One clone is
+// added to the interpreter as a hook, another writes the lcov .info file once the program stops.
+#[derive(Clone)]
+pub struct Coverage {
+    path: String,
+    merge: bool,
+    counts: Rc<RefCell<Counts>>
+}
+
+impl Coverage {
+
+    // With merge the counts of an existing file at path are added to the counts of this run
+    pub fn new(path: &str, merge: bool) -> Self {
+        Self {
+            path: path.to_string(),
+            merge,
+            counts: Rc::new(RefCell::new(Counts::default()))
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut files = self.counts.borrow().run_coverage();
+        if self.merge {
+            let text = match fs::read_to_string(&self.path) {
+                Ok(t) => t,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(e)
+            };
+            for (name, previous) in parse_lcov(&text) {
+                let file = files.entry(name).or_default();
+                for (line, hits) in previous.lines {
+                    *file.lines.entry(line).or_insert(0) += hits;
+                }
+                for (branch, taken) in previous.branches {
+                    let current = file.branches.entry(branch).or_insert(None);
+                    *current = match (*current, taken) {
+                        (Some(t), Some(v)) => Some(t + v),
+                        (t, None) => t,
+                        (None, v) => v
+                    };
+                }
+            }
+        }
+        fs::write(&self.path, format_lcov(&files))
+    }
+}
+
+impl Counts {
+
+    fn collect_statements(&mut self, ast: &ArenaTree<AstItem>, index: usize) {
+        let node = &ast.arena[index];
+        let source_info = node.val.source_info().cloned().unwrap_or_default();
+        self.statements.push((index, source_info.clone()));
+        if let AstItem::For(_) = node.val {
+            self.loops.push((index, source_info));
+            for child in &ast.arena[node.children[2]].children {
+                self.collect_statements(ast, *child);
+            }
+        }
+    }
+
+    fn is_loop(&self, index: usize) -> bool {
+        self.loops.iter().any(|(i, _)| *i == index)
+    }
+
+    fn close_statement(&mut self) {
+        let active = match self.active.pop() {
+            Some(t) => t,
+            None => return
+        };
+        if self.is_loop(active.index) {
+            let counts = if active.entered { &mut self.entered } else { &mut self.skipped };
+            *counts.entry(active.index).or_insert(0) += 1;
+        }
+    }
+
+    fn run_coverage(&self) -> BTreeMap<String, FileCoverage> {
+        let mut files: BTreeMap<String, FileCoverage> = BTreeMap::new();
+        for (index, source_info) in &self.statements {
+            let hits = self.hits.get(index).copied().unwrap_or(0);
+            let line = files.entry(source_info.file.to_string()).or_default().lines.entry(source_info.line).or_insert(0);
+            // Several statements on one line count as often as the most executed one
+            *line = (*line).max(hits);
+        }
+        let mut blocks: HashMap<String, usize> = HashMap::new();
+        for (index, source_info) in &self.loops {
+            let block = blocks.entry(source_info.file.to_string()).or_insert(0);
+            let file = files.entry(source_info.file.to_string()).or_default();
+            // Branch 0 is the body being entered, branch 1 the loop running zero times
+            let executed = self.hits.contains_key(index);
+            let entered = self.entered.get(index).copied().unwrap_or(0);
+            let skipped = self.skipped.get(index).copied().unwrap_or(0);
+            file.branches.insert((source_info.line, *block, 0), if executed { Some(entered) } else { None });
+            file.branches.insert((source_info.line, *block, 1), if executed { Some(skipped) } else { None });
+            *block += 1;
+        }
+        files
+    }
+}
+
+impl ExecutionHook for Coverage {
+    fn before_statement(&mut self, context: StatementContext) -> Result<(), RuntimeError> {
+        let mut counts = self.counts.borrow_mut();
+        // Statements that never run are reported too, so the whole program is listed up front
+        if counts.statements.is_empty() {
+            for child in &context.ast.arena[0].children {
+                counts.collect_statements(context.ast, *child);
+            }
+        }
+        if let Some(parent) = counts.active.last_mut() {
+            parent.entered = true;
+        }
+        *counts.hits.entry(context.index).or_insert(0) += 1;
+        counts.active.push(ActiveStatement { index: context.index, entered: false });
+        Ok(())
+    }
+
+    fn after_statement(&mut self, _index: usize) {
+        self.counts.borrow_mut().close_statement();
+    }
+
+    fn finished(&mut self) {
+        let mut counts = self.counts.borrow_mut();
+        while !counts.active.is_empty() {
+            counts.close_statement();
+        }
+    }
+}
+
+fn format_lcov(files: &BTreeMap<String, FileCoverage>) -> String {
+    let mut text = String::new();
+    for (name, file) in files {
+        text.push_str("TN:\n");
+        text.push_str(&format!("SF:{}\n", name));
+        for (line, hits) in &file.lines {
+            text.push_str(&format!("DA:{},{}\n", line, hits));
+        }
+        text.push_str(&format!("LF:{}\n", file.lines.len()));
+        text.push_str(&format!("LH:{}\n", file.lines.values().filter(|hits| **hits > 0).count()));
+        for ((line, block, branch), taken) in &file.branches {
+            let taken = match taken {
+                Some(t) => t.to_string(),
+                None => "-".to_string()
+            };
+            text.push_str(&format!("BRDA:{},{},{},{}\n", line, block, branch, taken));
+        }
+        text.push_str(&format!("BRF:{}\n", file.branches.len()));
+        text.push_str(&format!("BRH:{}\n", file.branches.values().filter(|taken| matches!(taken, Some(t) if *t > 0)).count()));
+        text.push_str("end_of_record\n");
+    }
+    text
+}
+
+// Reads back the records written by format_lcov, other lcov entries are ignored
+fn parse_lcov(text: &str) -> BTreeMap<String, FileCoverage> {
+    let mut files: BTreeMap<String, FileCoverage> = BTreeMap::new();
+    let mut current: Option<String> = None;
+    for line in text.lines() {
+        if let Some(name) = line.strip_prefix("SF:") {
+            current = Some(name.to_string());
+            files.entry(name.to_string()).or_default();
+            continue;
+        }
+        if line == "end_of_record" {
+            current = None;
+            continue;
+        }
+        let file = match &current {
+            Some(t) => files.get_mut(t).unwrap(),
+            None => continue
+        };
+        if let Some(data) = line.strip_prefix("DA:") {
+            let fields: Vec<&str> = data.split(',').collect();
+            if fields.len() < 2 {
+                continue;
+            }
+            if let (Ok(line), Ok(hits)) = (fields[0].parse(), fields[1].parse::<u64>()) {
+                *file.lines.entry(line).or_insert(0) += hits;
+            }
+        } else if let Some(data) = line.strip_prefix("BRDA:") {
+            let fields: Vec<&str> = data.split(',').collect();
+            if fields.len() != 4 {
+                continue;
+            }
+            if let (Ok(line), Ok(block), Ok(branch)) = (fields[0].parse(), fields[1].parse(), fields[2].parse()) {
+                file.branches.insert((line, block, branch), fields[3].parse().ok());
+            }
+        }
+    }
+    files
+}
@@ -5,7 +5,6 @@ pub struct Node<T>
 where
     T: PartialEq,
 {
-    idx: usize,
     pub val: T,
     pub parent: Option<usize>,
     pub children: Vec<usize>,
@@ -15,9 +14,8 @@ impl<T> Node<T>
 where
     T: PartialEq,
 {
-    fn new(idx: usize, val: T) -> Self {
+    fn new(val: T) -> Self {
         Self {
-            idx,
             val,
             parent: None,
             children: vec![],
@@ -46,7 +44,7 @@ where
     //    }
         // Otherwise, add new node
         let idx = self.arena.len();
-        self.arena.push(Node::new(idx, val));
+        self.arena.push(Node::new(val));
         idx
     }
     pub fn size(&self) -> usize {
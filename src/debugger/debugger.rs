@@ -0,0 +1,278 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::interpreter::hooks::{StatementContext, ExecutionHook};
+use crate::interpreter::interpreter::RunTimeVariable;
+use crate::interpreter::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::interpreter::value::Value;
+use crate::language::ast::{SourceInfo, VariableType};
+
+const HELP: &str = "\
+commands:
+  break [file:]LINE   stop before statements on LINE (b)
+  delete [file:]LINE  remove a breakpoint (d)
+  breakpoints         list breakpoints
+  step                run until the next statement, entering loop bodies (s)
+  next                run until the next statement at this depth or above (n)
+  continue            run until a breakpoint (c)
+  list                show the source around the current statement (l)
+  print NAME[INDEX]   show a variable or an element (p)
+  set NAME[INDEX] VALUE  change a variable or an element
+  vars                show all variables
+  quit                stop the program (q)
+";
+
+enum Mode {
+    Step,
+    Next(usize), // Stop at a depth not deeper than this
+    Continue
+}
+
+// Interactive debugger for the tree-walking interpreter. Commands are read from the program's
+// input and answers are written to its output, so they interleave with read and print.
+pub struct Debugger {
+    main_file: String,
+    breakpoints: HashSet<(String, i32)>,
+    mode: Mode,
+    sources: HashMap<String, Vec<String>>
+}
+
+impl Debugger {
+
+    pub fn new(main_file: &str) -> Self {
+        Self {
+            main_file: main_file.to_string(),
+            breakpoints: HashSet::new(),
+            mode: Mode::Step, // Pause before the first statement so breakpoints can be set
+            sources: HashMap::new()
+        }
+    }
+
+    fn should_stop(&self, source_info: &SourceInfo, depth: usize) -> bool {
+        match self.mode {
+            Mode::Step => true,
+            Mode::Next(t) if depth <= t => true,
+            _ => self.breakpoints.iter().any(|(file, line)| *line == source_info.line && same_file(&source_info.file, file))
+        }
+    }
+
+    fn prompt(&mut self, context: StatementContext, source_info: &SourceInfo) -> Result<(), RuntimeError> {
+        let io_error = |e: std::io::Error| RuntimeError::new(RuntimeErrorKind::Io(e.to_string()), source_info.clone());
+        let stopped = format!("stopped at {}\n{}", source_info, self.source_line(source_info, source_info.line, true));
+        writeln!(context.output, "{}", stopped).map_err(io_error)?;
+        loop {
+            write!(context.output, "(debug) ").and_then(|_| context.output.flush()).map_err(io_error)?;
+            let mut line = String::new();
+            if context.input.read_line(&mut line).map_err(io_error)? == 0 {
+                return Err(RuntimeError::new(RuntimeErrorKind::DebuggerQuit, source_info.clone()));
+            }
+            let mut words = line.trim().splitn(2, ' ');
+            let command = words.next().unwrap_or("");
+            let argument = words.next().unwrap_or("").trim();
+            let answer = match command {
+                "" => continue,
+                "s" | "step" => {
+                    self.mode = Mode::Step;
+                    return Ok(());
+                }
+                "n" | "next" => {
+                    self.mode = Mode::Next(context.depth);
+                    return Ok(());
+                }
+                "c" | "continue" => {
+                    self.mode = Mode::Continue;
+                    return Ok(());
+                }
+                "q" | "quit" => return Err(RuntimeError::new(RuntimeErrorKind::DebuggerQuit, source_info.clone())),
+                "b" | "break" => {
+                    match self.location(argument) {
+                        Some(t) => {
+                            let answer = format!("breakpoint at {}:{}", t.0, t.1);
+                            self.breakpoints.insert(t);
+                            answer
+                        }
+                        None => format!("invalid location \"{}\", expected [file:]LINE", argument)
+                    }
+                }
+                "d" | "delete" => {
+                    match self.location(argument) {
+                        Some(t) if self.breakpoints.remove(&t) => format!("deleted breakpoint at {}:{}", t.0, t.1),
+                        Some(t) => format!("no breakpoint at {}:{}", t.0, t.1),
+                        None => format!("invalid location \"{}\", expected [file:]LINE", argument)
+                    }
+                }
+                "breakpoints" => {
+                    let mut breakpoints: Vec<String> = self.breakpoints.iter().map(|(file, line)| format!("{}:{}", file, line)).collect();
+                    breakpoints.sort();
+                    if breakpoints.is_empty() {
+                        "no breakpoints".to_string()
+                    } else {
+                        breakpoints.join("\n")
+                    }
+                }
+                "l" | "list" => {
+                    let mut lines = Vec::new();
+                    for line in (source_info.line - 3).max(1)..=source_info.line + 3 {
+                        let text = self.source_line(source_info, line, line == source_info.line);
+                        if !text.is_empty() {
+                            lines.push(text);
+                        }
+                    }
+                    lines.join("\n")
+                }
+                "p" | "print" => inspect(context.variables, argument),
+                "set" => {
+                    let (target, value) = argument.split_once(' ').unwrap_or((argument, ""));
+                    modify(context.variables, target, value.trim())
+                }
+                "vars" => {
+                    let mut names: Vec<&String> = context.variables.keys().collect();
+                    names.sort();
+                    let lines: Vec<String> = names.iter().map(|name| describe(name, &context.variables[*name])).collect();
+                    if lines.is_empty() {
+                        "no variables".to_string()
+                    } else {
+                        lines.join("\n")
+                    }
+                }
+                "h" | "help" => HELP.trim_end().to_string(),
+                t => format!("unknown command \"{}\", try help", t)
+            };
+            writeln!(context.output, "{}", answer).map_err(io_error)?;
+        }
+    }
+
+    // Parses [file:]LINE, the file defaults to the program given on the command line
+    fn location(&self, text: &str) -> Option<(String, i32)> {
+        let (file, line) = match text.rsplit_once(':') {
+            Some((file, line)) => (file.to_string(), line),
+            None => (self.main_file.clone(), text)
+        };
+        match line.parse() {
+            Ok(t) if t > 0 => Some((file, t)),
+            _ => None
+        }
+    }
+
+    fn source_line(&mut self, source_info: &SourceInfo, line: i32, current: bool) -> String {
+        let file = source_info.file.to_string();
+        let lines = self.sources.entry(file.clone()).or_insert_with(|| {
+            match std::fs::read_to_string(&file) {
+                Ok(t) => t.lines().map(|l| l.to_string()).collect(),
+                Err(_) => Vec::new()
+            }
+        });
+        match lines.get((line - 1) as usize) {
+            Some(text) => format!("{} {:>4} | {}", if current { "->" } else { "  " }, line, text),
+            None => String::new()
+        }
+    }
+}
+
+impl ExecutionHook for Debugger {
+    fn before_statement(&mut self, context: StatementContext) -> Result<(), RuntimeError> {
+        let source_info = match context.ast.arena[context.index].val.source_info() {
+            Some(t) => t.clone(),
+            None => return Ok(())
+        };
+        if self.should_stop(&source_info, context.depth) {
+            self.prompt(context, &source_info)?;
+        }
+        Ok(())
+    }
+}
+
+// Breakpoint files may be given relative to any parent directory of the included file
+fn same_file(path: &str, file: &str) -> bool {
+    path == file || path.ends_with(&format!("/{}", file))
+}
+
+fn describe(name: &str, variable: &RunTimeVariable) -> String {
+    format!("{} : {} = {}", name, variable.var_type, quoted(&variable.value))
+}
+
+fn quoted(value: &Value) -> String {
+    match value {
+        Value::String(t) => format!("{:?}", t),
+        Value::Array(elements) => {
+            let elements: Vec<String> = elements.iter().map(quoted).collect();
+            format!("[{}]", elements.join(", "))
+        }
+        t => t.to_string()
+    }
+}
+
+// Splits NAME[INDEX] into the name and the optional index
+fn target(text: &str) -> Result<(&str, Option<usize>), String> {
+    match text.split_once('[') {
+        Some((name, rest)) => {
+            match rest.strip_suffix(']').and_then(|t| t.trim().parse().ok()) {
+                Some(t) => Ok((name.trim(), Some(t))),
+                None => Err(format!("invalid index in \"{}\"", text))
+            }
+        }
+        None => Ok((text, None))
+    }
+}
+
+fn inspect(variables: &HashMap<String, RunTimeVariable>, text: &str) -> String {
+    let (name, index) = match target(text) {
+        Ok(t) => t,
+        Err(e) => return e
+    };
+    let variable = match variables.get(name) {
+        Some(t) => t,
+        None => return format!("no variable {}", name)
+    };
+    match (index, &variable.value) {
+        (None, _) => describe(name, variable),
+        (Some(i), Value::Array(elements)) if i < elements.len() => format!("{}[{}] = {}", name, i, quoted(&elements[i])),
+        (Some(i), Value::String(t)) if i < t.chars().count() => format!("{}[{}] = {:?}", name, i, t.chars().nth(i).unwrap().to_string()),
+        (Some(i), _) => format!("cannot index {} with {}", name, i)
+    }
+}
+
+fn modify(variables: &mut HashMap<String, RunTimeVariable>, text: &str, value_text: &str) -> String {
+    let (name, index) = match target(text) {
+        Ok(t) => t,
+        Err(e) => return e
+    };
+    let variable = match variables.get_mut(name) {
+        Some(t) => t,
+        None => return format!("no variable {}", name)
+    };
+    let value_type = match (&variable.var_type, index) {
+        (VariableType::Array(element_type, _), Some(_)) => element_type.as_ref().clone(),
+        (_, Some(_)) => return format!("only array elements can be set by index, {} is {}", name, variable.var_type),
+        (VariableType::Array(_, _), None) => return "arrays can only be set element by element".to_string(),
+        (t, None) => t.clone()
+    };
+    let value = match parse_value(&value_type, value_text) {
+        Some(t) => t,
+        None => return format!("invalid {} value \"{}\"", value_type, value_text)
+    };
+    match (index, &mut variable.value) {
+        (None, slot) => *slot = value,
+        (Some(i), Value::Array(elements)) if i < elements.len() => elements[i] = value,
+        (Some(i), _) => return format!("index {} out of bounds for {}", i, name)
+    }
+    describe(name, variable)
+}
+
+fn parse_value(var_type: &VariableType, text: &str) -> Option<Value> {
+    match var_type {
+        VariableType::Int => text.parse().ok().map(Value::Int),
+        VariableType::Bool => match text {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None
+        },
+        VariableType::String => {
+            // Quotes are optional, so that strings with surrounding spaces can be entered
+            match text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+                Some(t) => Some(Value::String(t.to_string())),
+                None => Some(Value::String(text.to_string()))
+            }
+        }
+        VariableType::Array(_, _) => None
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use crate::checker::assignment_checker::AssignmentChecker;
+use crate::checker::type_checker::TypeChecker;
+use crate::data_structures::tree::ArenaTree;
+use crate::interpreter::arithmetic::IntegerMode;
+use crate::interpreter::hooks::ExecutionHook;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::limits::Limits;
+use crate::interpreter::natives::NativeRegistry;
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::interpreter::snapshot::Snapshot;
+use crate::interpreter::streams::LineStdin;
+use crate::interpreter::value::Value;
+use crate::language::ast::AstItem;
+use crate::language::lex::LexItem;
+use crate::loader::source_loader::SourceLoader;
+use crate::parser::syntax_parser::SyntaxParser;
+use crate::vm::compiler::Compiler;
+use crate::vm::machine::VirtualMachine;
+
+/// Which implementation executes the program. Both produce the same output and errors.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Backend {
+    /// Walks the syntax tree. Required for hooks, checkpoints and snapshots.
+    #[default]
+    Tree,
+    /// Compiles to bytecode first, faster for loop heavy programs.
+    Vm
+}
+
+impl Backend {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tree" => Some(Backend::Tree),
+            "vm" => Some(Backend::Vm),
+            _ => None
+        }
+    }
+}
+
+/// The result of a run that did not fail.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    /// False when a checkpoint paused the program before its end.
+    pub finished: bool,
+    /// The value of every declared variable when the program stopped.
+    pub variables: HashMap<String, Value>
+}
+
+/// Why a program could not be run to its end. Every message names the source location.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The source or an included file or a snapshot could not be read.
+    Load(String),
+    Syntax(String),
+    Type(String),
+    Runtime(RuntimeError),
+    /// The requested features need the tree backend.
+    Unsupported(String)
+}
+
+impl Error {
+    /// The process exit code the command line interpreter uses for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Runtime(e) => e.exit_code(),
+            _ => 1
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Load(t) | Error::Syntax(t) | Error::Type(t) | Error::Unsupported(t) => write!(f, "{}", t),
+            Error::Runtime(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+type Checkpoint = Box<dyn FnMut(&Interpreter) -> bool>;
+
+/// Runs Mini-PL programs. Configure it with the `with_` methods, then call `run` or
+/// `run_file` once.
+///
+/// By default programs read from stdin, print to stdout, use the tree backend with checked
+/// 32-bit integers and have no resource limits.
+pub struct Engine {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    backend: Backend,
+    integer_mode: IntegerMode,
+    limits: Limits,
+    strict: bool,
+    natives: NativeRegistry,
+    hooks: Vec<Box<dyn ExecutionHook>>,
+    checkpoint: Option<Checkpoint>,
+    restore: Option<Snapshot>
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+
+    pub fn new() -> Self {
+        Self {
+            // Locks stdin only while a read statement takes a line, not for the engine's lifetime
+            input: Box::new(LineStdin::default()),
+            output: Box::new(std::io::stdout()),
+            backend: Backend::default(),
+            integer_mode: IntegerMode::default(),
+            limits: Limits::default(),
+            strict: false,
+            natives: NativeRegistry::new(),
+            hooks: Vec::new(),
+            checkpoint: None,
+            restore: None
+        }
+    }
+
+    /// Where `read` takes its input from and `print` writes to.
+    pub fn with_io(mut self, input: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
+        self.input = input;
+        self.output = output;
+        self
+    }
+
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn with_integer_mode(mut self, mode: IntegerMode) -> Self {
+        self.integer_mode = mode;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Also rejects programs that may read a variable before assigning it.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Functions of the host application that programs can call. The type checker validates
+    /// calls against their registered signatures.
+    pub fn with_natives(mut self, natives: NativeRegistry) -> Self {
+        self.natives = natives;
+        self
+    }
+
+    /// Observes the execution, e.g. a tracer, profiler or debugger. Tree backend only.
+    pub fn with_hook(mut self, hook: Box<dyn ExecutionHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Called before each statement, returning false pauses the program. Tree backend only.
+    pub fn with_checkpoint(mut self, checkpoint: impl FnMut(&Interpreter) -> bool + 'static) -> Self {
+        self.checkpoint = Some(Box::new(checkpoint));
+        self
+    }
+
+    /// Resumes the program from a snapshot instead of its start. Tree backend only.
+    pub fn with_restore(mut self, snapshot: Snapshot) -> Self {
+        self.restore = Some(snapshot);
+        self
+    }
+
+    /// Runs a program given as text. Includes are resolved relative to the current directory.
+    pub fn run(self, source: &str) -> Result<Outcome, Error> {
+        let tokens = SourceLoader::new().load_source(source.to_string(), "<source>").map_err(Error::Load)?;
+        self.run_tokens(tokens)
+    }
+
+    /// Runs the program in a file. Includes are resolved relative to that file.
+    pub fn run_file(self, path: &Path) -> Result<Outcome, Error> {
+        let tokens = SourceLoader::new().load(path).map_err(Error::Load)?;
+        self.run_tokens(tokens)
+    }
+
+    fn run_tokens(self, tokens: Vec<LexItem>) -> Result<Outcome, Error> {
+        if self.backend == Backend::Vm && (!self.hooks.is_empty() || self.checkpoint.is_some() || self.restore.is_some()) {
+            return Err(Error::Unsupported("ERROR hooks, checkpoints and snapshots only support the tree backend".to_string()));
+        }
+        let ast = parse(tokens)?;
+        TypeChecker::new(&ast).with_natives(&self.natives).check().map_err(Error::Type)?;
+        if self.strict {
+            AssignmentChecker::new(&ast).check().map_err(Error::Type)?;
+        }
+        match self.backend {
+            Backend::Vm => {
+                let mut compiler = Compiler::new(&ast);
+                compiler.set_integer_mode(self.integer_mode);
+                let mut machine = VirtualMachine::with_io(compiler.compile(), self.input, self.output);
+                machine.set_integer_mode(self.integer_mode);
+                machine.set_limits(self.limits);
+                machine.set_natives(self.natives);
+                machine.run().map_err(Error::Runtime)?;
+                Ok(Outcome { finished: true, variables: machine.variables() })
+            }
+            Backend::Tree => {
+                let mut interpreter = Interpreter::with_io(ast, self.input, self.output);
+                interpreter.set_integer_mode(self.integer_mode);
+                interpreter.set_limits(self.limits);
+                interpreter.set_natives(self.natives);
+                for hook in self.hooks {
+                    interpreter.add_hook(hook);
+                }
+                if let Some(snapshot) = self.restore {
+                    interpreter.restore(snapshot).map_err(Error::Load)?;
+                }
+                let finished = match self.checkpoint {
+                    Some(mut checkpoint) => interpreter.interpret_with(&mut *checkpoint),
+                    None => interpreter.interpret().map(|_| true)
+                }.map_err(Error::Runtime)?;
+                let variables = interpreter.variables().iter().map(|(name, t)| (name.clone(), t.value.clone())).collect();
+                Ok(Outcome { finished, variables })
+            }
+        }
+    }
+}
+
+fn parse(tokens: Vec<LexItem>) -> Result<ArenaTree<AstItem>, Error> {
+    if tokens.is_empty() {
+        // An empty program does nothing, the parser only accepts at least one token
+        let mut ast = ArenaTree::default();
+        ast.node(AstItem::Root);
+        return Ok(ast);
+    }
+    SyntaxParser::new(tokens).parse().map_err(Error::Syntax)
+}
@@ -0,0 +1,119 @@
+use num_bigint::BigInt;
+
+use crate::language::ast::{BinOpType, SourceInfo};
+use super::runtime_error::{RuntimeError, RuntimeErrorKind};
+use super::value::Value;
+
+// What happens when an int operation leaves the i32 range. Division by zero is an error in
+// every mode. In big mode the result becomes a Value::Big and stays exact.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum IntegerMode {
+    #[default]
+    Checked,
+    Wrapping,
+    Saturating,
+    Big
+}
+
+impl IntegerMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "checked" => Some(IntegerMode::Checked),
+            "wrapping" => Some(IntegerMode::Wrapping),
+            "saturating" => Some(IntegerMode::Saturating),
+            "big" => Some(IntegerMode::Big),
+            _ => None
+        }
+    }
+}
+
+pub fn int_operation(op: &BinOpType, left: i32, right: i32, mode: IntegerMode, source_info: &SourceInfo) -> Result<i32, RuntimeError> {
+    if *op == BinOpType::Divide && right == 0 {
+        return Err(RuntimeError::new(RuntimeErrorKind::DivisionByZero, source_info.clone()));
+    }
+    let result = match (mode, op) {
+        (IntegerMode::Checked, BinOpType::Plus) => left.checked_add(right),
+        (IntegerMode::Checked, BinOpType::Minus) => left.checked_sub(right),
+        (IntegerMode::Checked, BinOpType::Multiply) => left.checked_mul(right),
+        (IntegerMode::Checked, BinOpType::Divide) => left.checked_div(right),
+        (IntegerMode::Wrapping, BinOpType::Plus) => Some(left.wrapping_add(right)),
+        (IntegerMode::Wrapping, BinOpType::Minus) => Some(left.wrapping_sub(right)),
+        (IntegerMode::Wrapping, BinOpType::Multiply) => Some(left.wrapping_mul(right)),
+        (IntegerMode::Wrapping, BinOpType::Divide) => Some(left.wrapping_div(right)),
+        (IntegerMode::Saturating, BinOpType::Plus) => Some(left.saturating_add(right)),
+        (IntegerMode::Saturating, BinOpType::Minus) => Some(left.saturating_sub(right)),
+        (IntegerMode::Saturating, BinOpType::Multiply) => Some(left.saturating_mul(right)),
+        (IntegerMode::Saturating, BinOpType::Divide) => Some(left.saturating_div(right)),
+        (IntegerMode::Big, _) => panic!("Error, big integer operations are not computed in i32"),
+        (_, op) => panic!("Error, {:?} is not an arithmetic operator", op)
+    };
+    match result {
+        Some(t) => Ok(t),
+        None => Err(RuntimeError::new(RuntimeErrorKind::IntegerOverflow(format!("{} {} {}", left, op, right)), source_info.clone()))
+    }
+}
+
+pub fn int_negate(value: i32, mode: IntegerMode, source_info: &SourceInfo) -> Result<i32, RuntimeError> {
+    let result = match mode {
+        IntegerMode::Checked => value.checked_neg(),
+        IntegerMode::Wrapping => Some(value.wrapping_neg()),
+        IntegerMode::Saturating => Some(value.saturating_neg()),
+        IntegerMode::Big => panic!("Error, big integer operations are not computed in i32")
+    };
+    match result {
+        Some(t) => Ok(t),
+        None => Err(RuntimeError::new(RuntimeErrorKind::IntegerOverflow(format!("-({})", value)), source_info.clone()))
+    }
+}
+
+pub fn big_operation(op: &BinOpType, left: BigInt, right: BigInt, source_info: &SourceInfo) -> Result<Value, RuntimeError> {
+    let result = match op {
+        BinOpType::Plus => left + right,
+        BinOpType::Minus => left - right,
+        BinOpType::Multiply => left * right,
+        BinOpType::Divide => {
+            if right == BigInt::from(0) {
+                return Err(RuntimeError::new(RuntimeErrorKind::DivisionByZero, source_info.clone()));
+            }
+            left / right
+        }
+        op => panic!("Error, {:?} is not an arithmetic operator", op)
+    };
+    Ok(int_value(result))
+}
+
+// Value of a for loop variable. Only the value after the last iteration can leave the i32 range,
+// it follows the integer mode like any other addition of the step.
+pub fn loop_value(counter: i64, step: i64, mode: IntegerMode, source_info: &SourceInfo) -> Result<Value, RuntimeError> {
+    match (i32::try_from(counter), mode) {
+        (Ok(t), _) => Ok(Value::Int(t)),
+        (Err(_), IntegerMode::Checked) => Err(RuntimeError::new(RuntimeErrorKind::IntegerOverflow(format!("{} + {}", counter - step, step)), source_info.clone())),
+        (Err(_), IntegerMode::Wrapping) => Ok(Value::Int(counter as i32)),
+        (Err(_), IntegerMode::Saturating) => Ok(Value::Int(counter.clamp(i32::MIN as i64, i32::MAX as i64) as i32)),
+        (Err(_), IntegerMode::Big) => Ok(Value::Big(BigInt::from(counter)))
+    }
+}
+
+// Ints that fit in i32 are kept as Value::Int so they can still be used as indices and ranges
+pub fn int_value(value: BigInt) -> Value {
+    match i32::try_from(&value) {
+        Ok(t) => Value::Int(t),
+        Err(_) => Value::Big(value)
+    }
+}
+
+pub fn to_big(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Int(t) => Some(BigInt::from(*t)),
+        Value::Big(t) => Some(t.clone()),
+        _ => None
+    }
+}
+
+pub fn parse_int(text: &str, mode: IntegerMode) -> Option<Value> {
+    match text.parse() {
+        Ok(t) => Some(Value::Int(t)),
+        Err(_) if mode == IntegerMode::Big => text.parse().ok().map(int_value),
+        Err(_) => None
+    }
+}
@@ -0,0 +1,87 @@
+use crate::language::ast::{SourceInfo, VariableType};
+use crate::language::builtins::builtin_signature;
+use crate::language::format::{parse_format, FormatPart};
+use super::arithmetic::{parse_int, IntegerMode};
+use super::natives::NativeRegistry;
+use super::runtime_error::{RuntimeError, RuntimeErrorKind};
+use super::value::Value;
+
+// Registering a native function with a built-in name fails, so natives are looked up first,
+// which costs nothing while none are registered
+pub fn call_function(name: &str, args: &[Value], mode: IntegerMode, natives: &NativeRegistry, source_info: &SourceInfo) -> Result<Value, RuntimeError> {
+    if natives.contains(name) {
+        natives.call(name, args, source_info)
+    } else {
+        call_builtin(name, args, mode, source_info)
+    }
+}
+
+// Implementations of the functions declared in language::builtins. Strings are indexed and
+// measured in Unicode scalar values, not bytes.
+pub fn call_builtin(name: &str, args: &[Value], mode: IntegerMode, source_info: &SourceInfo) -> Result<Value, RuntimeError> {
+    match (name, args) {
+        ("len", [Value::String(t)]) => Ok(Value::Int(t.chars().count() as i32)),
+        ("substr", [Value::String(t), Value::Int(start), Value::Int(count)]) => {
+            let length = t.chars().count();
+            if *start < 0 || *count < 0 || *start as i64 + *count as i64 > length as i64 {
+                return Err(RuntimeError::new(RuntimeErrorKind::SubstrOutOfBounds {start: *start, count: *count, length}, source_info.clone()));
+            }
+            Ok(Value::String(t.chars().skip(*start as usize).take(*count as usize).collect()))
+        }
+        ("indexOf", [Value::String(t), Value::String(v)]) => {
+            match t.find(v.as_str()) {
+                Some(byte_index) => Ok(Value::Int(t[..byte_index].chars().count() as i32)),
+                None => Ok(Value::Int(-1))
+            }
+        }
+        ("upper", [Value::String(t)]) => Ok(Value::String(t.to_uppercase())),
+        ("lower", [Value::String(t)]) => Ok(Value::String(t.to_lowercase())),
+        ("toString", [t @ (Value::Int(_) | Value::Big(_))]) => Ok(Value::String(t.to_string())),
+        ("toInt", [Value::String(t)]) => {
+            match parse_int(t.trim(), mode) {
+                Some(v) => Ok(v),
+                None => Err(RuntimeError::new(RuntimeErrorKind::InvalidConversion(t.clone()), source_info.clone()))
+            }
+        }
+        ("format", [Value::String(t), values @ ..]) => format_values(t, values, source_info),
+        _ if builtin_signature(name).is_none() => {
+            Err(RuntimeError::new(RuntimeErrorKind::UndefinedFunction(name.to_string()), source_info.clone()))
+        }
+        _ => {
+            let found: Vec<String> = args.iter().map(|t| t.to_string()).collect();
+            Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: format!("valid arguments for {}", name), found: found.join(", ")}, source_info.clone()))
+        }
+    }
+}
+
+fn format_values(format: &str, values: &[Value], source_info: &SourceInfo) -> Result<Value, RuntimeError> {
+    let invalid = |message: String| RuntimeError::new(RuntimeErrorKind::InvalidFormat(message), source_info.clone());
+    let parts = parse_format(format).map_err(invalid)?;
+    let mut values = values.iter();
+    let mut result = String::new();
+    let mut number = 0;
+    for part in &parts {
+        let field = match part {
+            FormatPart::Text(t) => {
+                result.push_str(t);
+                continue;
+            }
+            FormatPart::Field(t) => t
+        };
+        number += 1;
+        let value = match values.next() {
+            Some(t) => t,
+            None => return Err(invalid(format!("no value for field {}", number)))
+        };
+        match (&field.value_type, value) {
+            (VariableType::Int, Value::Int(_) | Value::Big(_)) | (VariableType::String, Value::String(_)) | (VariableType::Bool, Value::Bool(_)) => {
+                result.push_str(&field.pad(&value.to_string()));
+            }
+            (t, v) => return Err(invalid(format!("field {} expects {}, found {}", number, t, v)))
+        }
+    }
+    if values.next().is_some() {
+        return Err(invalid("more values than fields".to_string()));
+    }
+    Ok(Value::String(result))
+}
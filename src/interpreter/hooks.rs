@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use crate::data_structures::tree::ArenaTree;
+use crate::language::ast::{AstItem, SourceInfo};
+use super::interpreter::RunTimeVariable;
+use super::runtime_error::RuntimeError;
+use super::value::Value;
+
+// Everything a hook can see and change when the interpreter is about to run a statement
+pub struct StatementContext<'a> {
+    pub ast: &'a ArenaTree<AstItem>,
+    pub index: usize,
+    pub depth: usize, // 0 for top level statements, +1 for each enclosing for loop
+    pub variables: &'a mut HashMap<String, RunTimeVariable>,
+    pub input: &'a mut dyn BufRead,
+    pub output: &'a mut dyn Write
+}
+
+// Observes execution in the tree-walking interpreter. Returning an error from before_statement
+// stops the program. Hooks that only report on the run must not change its outcome, so they
+// ignore failed writes to their own output.
+pub trait ExecutionHook {
+    fn before_statement(&mut self, context: StatementContext) -> Result<(), RuntimeError>;
+
+    // Only called when the statement completed without an error
+    fn after_statement(&mut self, _index: usize) {}
+
+    // Called once when the program stops, whether it succeeded or not
+    fn finished(&mut self) {}
+
+    fn expression_evaluated(&mut self, _node: &AstItem, _value: &Value) {}
+
+    // index is set when a single array element was written
+    fn variable_written(&mut self, _name: &str, _index: Option<usize>, _value: &Value, _source_info: &SourceInfo) {}
+}
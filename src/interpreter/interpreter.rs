@@ -1,409 +1,580 @@
-use core::fmt;
-use std::{collections::HashMap, io::Write};
+use std::{cell::RefCell, collections::HashMap, io::{BufRead, Cursor, Write}, rc::Rc};
 
-use crate::{data_structures::tree::{ArenaTree, Node}, language::ast::{AstItem, VariableType, BinOpType}};
+use crate::{data_structures::tree::{ArenaTree, Node}, language::{ast::{AstItem, VariableType, SourceInfo}, source_text::expression_text}};
+use super::arithmetic::{loop_value, IntegerMode};
+use super::builtins::call_function;
+use super::hooks::{StatementContext, ExecutionHook};
+use super::limits::{LimitTracker, Limits};
+use super::natives::NativeRegistry;
+use super::operations::{assert_operand_text, assert_operands, assert_condition, assertion_failed, binary_operation, check_assert, check_index, constant_value, element_at, expect_int, has_type, initial_value, negate, not, print_value, read_value, short_circuit};
+use super::runtime_error::{RuntimeError, RuntimeErrorKind};
+use super::snapshot::{fingerprint, Frame, LoopState, Snapshot};
+use super::streams::OutputBuffer;
+use super::value::Value;
 
+// Nodes are visited by their index in the arena and borrowed from it, never cloned
 pub struct Interpreter {
-    ast: ArenaTree<AstItem>,
-    variables: HashMap<String, RunTimeVariable>
+    ast: Rc<ArenaTree<AstItem>>,
+    variables: HashMap<String, RunTimeVariable>,
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    integer_mode: IntegerMode,
+    hooks: RefCell<Vec<Box<dyn ExecutionHook>>>, // Expressions are evaluated through &self
+    has_hooks: bool, // Skips the per-expression dispatch, which is on the hot path
+    limits: LimitTracker,
+    natives: NativeRegistry,
+    frames: Vec<Frame> // Empty until the program starts and once it has finished
 }
 
 impl Interpreter {
 
+    // Reads from stdin and prints to stdout
     pub fn new(ast: ArenaTree<AstItem>) -> Self {
+        Self::with_io(ast, Box::new(std::io::stdin().lock()), Box::new(std::io::stdout()))
+    }
+
+    pub fn with_io(ast: ArenaTree<AstItem>, input: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
         Self {
-            ast,
-            variables: HashMap::new()
+            ast: Rc::new(ast),
+            variables: HashMap::new(),
+            input,
+            output,
+            integer_mode: IntegerMode::default(),
+            hooks: RefCell::new(Vec::new()),
+            has_hooks: false,
+            limits: LimitTracker::new(Limits::default()),
+            natives: NativeRegistry::new(),
+            frames: Vec::new()
         }
     }
-    pub fn interpret(&mut self) {
-        //let root = self.ast.arena[0].clone();
-        let children = self.ast.arena[0].children.clone();
-        for child in children {
-            let node = self.ast.arena[child].clone();
-            self.parse_node(node);
+
+    // Reads from the given text and collects everything printed into the returned buffer
+    pub fn with_buffers(ast: ArenaTree<AstItem>, input: &str) -> (Self, OutputBuffer) {
+        let output = OutputBuffer::new();
+        let interpreter = Self::with_io(ast, Box::new(Cursor::new(input.as_bytes().to_vec())), Box::new(output.clone()));
+        (interpreter, output)
+    }
+
+    pub fn set_integer_mode(&mut self, mode: IntegerMode) {
+        self.integer_mode = mode;
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = LimitTracker::new(limits);
+    }
+
+    pub fn set_natives(&mut self, natives: NativeRegistry) {
+        self.natives = natives;
+    }
+
+    pub fn add_hook(&mut self, hook: Box<dyn ExecutionHook>) {
+        self.hooks.get_mut().push(hook);
+        self.has_hooks = true;
+    }
+
+    pub fn interpret(&mut self) -> Result<(), RuntimeError> {
+        self.interpret_with(&mut |_| true).map(|_| ())
+    }
+
+    // checkpoint is called before each statement and pauses the program by returning false.
+    // Returns whether the program finished; a paused one continues on the next call.
+    pub fn interpret_with(&mut self, checkpoint: &mut dyn FnMut(&Interpreter) -> bool) -> Result<bool, RuntimeError> {
+        self.limits.start();
+        if self.frames.is_empty() {
+            self.frames.push(Frame { block: 0, next: 0, for_loop: None });
         }
+        let result = self.run(checkpoint);
+        for hook in self.hooks.get_mut().iter_mut() {
+            hook.finished();
+        }
+        result
     }
 
-    fn parse_node(&mut self, node: Node<AstItem>) {
-        match node.val {
-            AstItem::Print => {
-                let child = self.ast.arena[node.children[0]].clone();
-                let value = self.expect_expr(child);
-                self.handle_print(value);
-            }
-            AstItem::Assign => {
-                self.handle_assign(node);
-            }
-            AstItem::Read => {
-                self.handle_read(node);
+    // Runs another program against the current variables, used by the REPL
+    pub fn interpret_more(&mut self, ast: ArenaTree<AstItem>) -> Result<(), RuntimeError> {
+        self.ast = Rc::new(ast);
+        self.frames.clear();
+        self.interpret()
+    }
+
+    // Evaluates a lone expression parsed by SyntaxParser::parse_expression
+    pub fn evaluate(&mut self, ast: ArenaTree<AstItem>) -> Result<Value, RuntimeError> {
+        self.ast = Rc::new(ast);
+        self.frames.clear();
+        self.expect_expr(self.ast.arena[0].children[0])
+    }
+
+    pub fn variables(&self) -> &HashMap<String, RunTimeVariable> {
+        &self.variables
+    }
+
+    // Adds a variable with its type's default value unless it already exists
+    pub fn declare(&mut self, name: &str, var_type: &VariableType) {
+        if !self.variables.contains_key(name) {
+            self.variables.insert(name.to_string(), RunTimeVariable {name: name.to_string(), var_type: var_type.clone(), value: initial_value(var_type)});
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let mut variables: Vec<(String, VariableType, Value)> = self.variables.iter()
+            .map(|(name, t)| (name.clone(), t.var_type.clone(), t.value.clone()))
+            .collect();
+        variables.sort_by(|a, b| a.0.cmp(&b.0));
+        Snapshot { fingerprint: fingerprint(&self.ast), variables, frames: self.frames.clone() }
+    }
+
+    pub fn restore(&mut self, snapshot: Snapshot) -> Result<(), String> {
+        if snapshot.fingerprint != fingerprint(&self.ast) {
+            return Err("ERROR the snapshot was taken from a different program".to_string());
+        }
+        for frame in &snapshot.frames {
+            let valid = match (&self.ast.arena.get(frame.block).map(|t| &t.val), &frame.for_loop) {
+                (Some(AstItem::Root), None) => true,
+                (Some(AstItem::Block), Some(t)) => matches!(self.ast.arena.get(t.statement).map(|t| &t.val), Some(AstItem::For(_))),
+                _ => false
+            };
+            if !valid {
+                return Err("ERROR the snapshot does not match the program".to_string());
             }
-            AstItem::Assert => {
-                self.handle_assert(node);
+        }
+        for (name, var_type, value) in &snapshot.variables {
+            let declared = self.ast.arena.iter().find_map(|t| match &t.val {
+                AstItem::Variable(v) if &v.name == name => Some(&v.var_type),
+                _ => None
+            });
+            match declared {
+                Some(t) if t != var_type => return Err(format!("ERROR the snapshot gives {} type {}, the program declares {}", name, var_type, t)),
+                Some(t) if !has_type(value, t) => return Err(format!("ERROR the snapshot value of {} is not of type {}", name, t)),
+                Some(_) => (),
+                None => return Err(format!("ERROR the snapshot has variable {}, which the program does not declare", name))
             }
-            AstItem::For => {
-                self.handle_for(node);
+        }
+        self.variables = snapshot.variables.into_iter()
+            .map(|(name, var_type, value)| (name.clone(), RunTimeVariable {name, var_type, value}))
+            .collect();
+        self.frames = snapshot.frames;
+        Ok(())
+    }
+
+    // The statement the program continues with, None between loop iterations or when finished
+    pub fn next_statement(&self) -> Option<&AstItem> {
+        let frame = self.frames.last()?;
+        let index = self.ast.arena[frame.block].children.get(frame.next)?;
+        Some(&self.ast.arena[*index].val)
+    }
+
+    fn run(&mut self, checkpoint: &mut dyn FnMut(&Interpreter) -> bool) -> Result<bool, RuntimeError> {
+        while let Some(frame) = self.frames.last() {
+            match self.ast.arena[frame.block].children.get(frame.next) {
+                Some(index) => {
+                    let index = *index;
+                    if !checkpoint(self) {
+                        return Ok(false);
+                    }
+                    self.frames.last_mut().unwrap().next += 1;
+                    self.parse_node(index)?;
+                }
+                None => self.end_block()?
             }
-            _ => panic!("Unexpected node {:#?}", node)
         }
+        Ok(true)
     }
 
-    fn handle_print(&self, value: Value) {
-        match value {
-            Value::String(t) => {
-                std::io::stdout().write(t.as_bytes()).unwrap();
+    // For statements finish when their last iteration ends, so they are completed here
+    fn parse_node(&mut self, index: usize) -> Result<(), RuntimeError> {
+        self.before_statement(index)?;
+        if let AstItem::For(_) = self.ast.arena[index].val {
+            return self.handle_for(index);
+        }
+        self.execute_statement(index)?;
+        self.after_statement(index);
+        Ok(())
+    }
+
+    fn after_statement(&mut self, index: usize) {
+        for hook in self.hooks.get_mut().iter_mut() {
+            hook.after_statement(index);
+        }
+    }
+
+    fn before_statement(&mut self, index: usize) -> Result<(), RuntimeError> {
+        self.limits.count_statement(source_info(&self.ast.arena[index]))?;
+        for hook in self.hooks.get_mut().iter_mut() {
+            hook.before_statement(StatementContext {
+                ast: &self.ast,
+                index,
+                depth: self.frames.len() - 1,
+                variables: &mut self.variables,
+                input: &mut *self.input,
+                output: &mut *self.output
+            })?;
+        }
+        Ok(())
+    }
+
+    fn execute_statement(&mut self, index: usize) -> Result<(), RuntimeError> {
+        match self.ast.arena[index].val {
+            AstItem::Print(_) | AstItem::Println(_) => {
+                self.handle_print(index)
             }
-            Value::Bool(t) => {
-                if t {
-                    std::io::stdout().write(b"true").unwrap();
-                } else {
-                    std::io::stdout().write(b"false").unwrap();
-                }
+            AstItem::Assign(_) => {
+                self.handle_assign(index)
             }
-            Value::Int(t) => {
-                std::io::stdout().write(t.to_string().as_bytes()).unwrap();
+            AstItem::Read(_) => {
+                self.handle_read(index)
             }
-            Value::NULL => {
-                std::io::stdout().write(b"NULL").unwrap();
+            AstItem::Assert(_) => {
+                self.handle_assert(index)
             }
+            _ => panic!("Unexpected node {:#?}", self.ast.arena[index])
         }
-        std::io::stdout().flush().expect("std flush failed");
     }
 
-    fn handle_for(&mut self, node: Node<AstItem>) {
-        let variable_node = self.ast.arena[node.children[0]].clone();
-        let range_node = self.ast.arena[node.children[1]].clone();
-        let block_node = self.ast.arena[node.children[2]].clone();
-        let variable_info;
-        let mut variable;
-        if let AstItem::Variable(t) = variable_node.clone().val {
-            variable_info = t;
-        } else {
-            panic!("ERROR for statement expected variable, found {:#?}", variable_node.val);
-        }
-        let variable_option = self.variables.get(&variable_info.name);
-        match variable_option {
-            Some(t) => variable = t.clone(),
-            None => panic!("Undefined variable {:#?}", variable_node.val)
-        }
-        let range_left_node = self.ast.arena[range_node.children[0]].clone();
-        let range_right_node = self.ast.arena[range_node.children[1]].clone();
-        let range_left_expr = self.expect_expr(range_left_node);
-        let range_right_expr = self.expect_expr(range_right_node);
-        let range_left;
-        let range_right;
-        match (range_left_expr.clone(), range_right_expr.clone()) {
-            (Value::Int(t), Value::Int(v)) => {
-                range_left = t;
-                range_right = v;
-            }
-            _ => {
-                panic!("ERROR range values are not int. Left = {}, Right = {}", range_left_expr, range_right_expr)
-            }
+    // All expressions are evaluated before anything is written
+    fn handle_print(&mut self, index: usize) -> Result<(), RuntimeError> {
+        let node = &self.ast.arena[index];
+        let mut values = Vec::with_capacity(node.children.len() + 1);
+        for child in &node.children {
+            values.push(self.expect_expr(*child)?);
+        }
+        if let AstItem::Println(_) = node.val {
+            values.push(Value::String("\n".to_string()));
+        }
+        for value in &values {
+            self.limits.count_output(value, source_info(node))?;
+            print_value(&mut self.output, value, source_info(node))?;
         }
+        Ok(())
+    }
 
-        variable.value = Value::Int(range_left);
-        self.variables.insert(variable.name.clone(), variable.clone());
-        for _ in range_left..=range_right {
-            let block_children = block_node.children.clone();
-            for child in block_children {
-                let node = self.ast.arena[child].clone();
-                self.parse_node(node);
+    fn handle_for(&mut self, index: usize) -> Result<(), RuntimeError> {
+        // Setting the control variable needs &mut self, so the loop keeps its own handle to the tree
+        let ast = Rc::clone(&self.ast);
+        let node = &ast.arena[index];
+        let range_node = &ast.arena[node.children[1]];
+        let variable_info = match &ast.arena[node.children[0]].val {
+            AstItem::Variable(t) => t,
+            t => panic!("ERROR for statement expected variable, found {:#?}", t)
+        };
+        if !self.variables.contains_key(&variable_info.name) {
+            return Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(variable_info.name.clone()), variable_info.source_info.clone()));
+        }
+        let range_left = self.expect_int(range_node.children[0])?;
+        let range_right = self.expect_int(range_node.children[1])?;
+        let mut step = 1;
+        if range_node.children.len() > 2 {
+            step = self.expect_int(range_node.children[2])?;
+            if step == 0 {
+                return Err(RuntimeError::new(RuntimeErrorKind::ZeroStep, source_info(&ast.arena[range_node.children[2]]).clone()));
             }
-            let old_value;
-            match variable.value {
-                Value::Int(t) => {
-                    old_value = t;
-                }
-                _ => {
-                    panic!("Loop variable is not int {:#?}", node);
-                }
+        }
+
+        // The control variable takes the values left, left + step, ... while they stay within
+        // the range. After the loop it holds the first value past the range (or left if the
+        // body never ran), see arithmetic::loop_value. Assignments to it inside the body are
+        // overwritten on each iteration.
+        let iterations = if step > 0 && range_left <= range_right {
+            (range_right as i64 - range_left as i64) / step as i64 + 1
+        } else if step < 0 && range_left >= range_right {
+            (range_left as i64 - range_right as i64) / -(step as i64) + 1
+        } else {
+            0
+        };
+        self.set_loop_variable(&variable_info.name, Value::Int(range_left), source_info(node));
+        if iterations == 0 {
+            self.after_statement(index);
+            return Ok(());
+        }
+        self.limits.check_time(source_info(node))?;
+        self.frames.push(Frame {
+            block: node.children[2],
+            next: 0,
+            for_loop: Some(LoopState {statement: index, counter: range_left as i64, remaining: iterations, step})
+        });
+        Ok(())
+    }
+
+    // Moves a loop to its next iteration, or leaves the block
+    fn end_block(&mut self) -> Result<(), RuntimeError> {
+        let frame = self.frames.last_mut().unwrap();
+        let state = match &mut frame.for_loop {
+            Some(t) => t,
+            None => {
+                self.frames.pop();
+                return Ok(());
+            }
+        };
+        let ast = Rc::clone(&self.ast);
+        let node = &ast.arena[state.statement];
+        let name = match &ast.arena[node.children[0]].val {
+            AstItem::Variable(t) => &t.name,
+            t => panic!("ERROR for statement expected variable, found {:#?}", t)
+        };
+        // Each finished iteration counts as a step, so an empty body cannot loop unchecked
+        self.limits.count_statement(source_info(node))?;
+        state.counter += state.step as i64;
+        state.remaining -= 1;
+        let (remaining, statement) = (state.remaining, state.statement);
+        let value = loop_value(state.counter, state.step as i64, self.integer_mode, source_info(node))?;
+        self.set_loop_variable(name, value, source_info(node));
+        if remaining > 0 {
+            self.frames.last_mut().unwrap().next = 0;
+        } else {
+            self.frames.pop();
+            self.after_statement(statement);
+        }
+        Ok(())
+    }
+
+    fn set_loop_variable(&mut self, name: &str, value: Value, source_info: &SourceInfo) {
+        if let Some(variable) = self.variables.get_mut(name) {
+            variable.value = value;
+            notify_write(self.hooks.get_mut(), &self.variables, name, None, source_info);
+        }
+    }
+
+    fn handle_assert(&mut self, index: usize) -> Result<(), RuntimeError> {
+        let node = &self.ast.arena[index];
+        let condition = node.children[0];
+        let operands = assert_operands(&self.ast, condition);
+        let mut values: Vec<Option<Value>> = Vec::with_capacity(operands.len());
+        for operand in &operands {
+            let skipped = match (&self.ast.arena[condition].val, values.first()) {
+                (AstItem::BinOp(t, _), Some(Some(left))) => short_circuit(t, left).is_some(),
+                _ => false
+            };
+            values.push(if skipped { None } else { Some(self.expect_expr(*operand)?) });
+        }
+        let whole = if operands.is_empty() { Some(self.expect_expr(condition)?) } else { None };
+        // The message is evaluated before the check, in the order the VM pushes it
+        let message = match node.children.get(1) {
+            Some(t) => Some(self.expect_expr(*t)?),
+            None => None
+        };
+        let value = match (&self.ast.arena[condition].val, whole, values.as_slice()) {
+            (_, Some(t), _) => t,
+            (AstItem::Variable(_), _, [Some(t)]) => t.clone(),
+            (AstItem::BinOp(t, source_info), _, [Some(left), right]) => assert_condition(t, left, right.as_ref(), self.integer_mode, source_info)?,
+            (AstItem::Not(source_info), _, [Some(t)]) => not(t.clone(), source_info)?,
+            (t, _, _) => panic!("Unexpected assert condition {:#?}", t)
+        };
+        // A bare variable was evaluated through expect_expr already
+        if self.has_hooks && matches!(self.ast.arena[condition].val, AstItem::BinOp(_, _) | AstItem::Not(_)) {
+            for hook in self.hooks.borrow_mut().iter_mut() {
+                hook.expression_evaluated(&self.ast.arena[condition].val, &value);
             }
-            variable.value = Value::Int(old_value + 1);
-            self.variables.insert(variable.name.clone(), variable.clone());
         }
-        
+        if check_assert(value, source_info(node))? {
+            return Ok(());
+        }
+        let reported = operands.iter().zip(&values)
+            .filter_map(|(operand, value)| assert_operand_text(&self.ast, *operand).map(|text| (text, value.as_ref())))
+            .collect();
+        Err(assertion_failed(expression_text(&self.ast, condition), reported, message, source_info(node)))
     }
 
-    fn handle_assert(&mut self, node: Node<AstItem>) {
-        let child = self.ast.arena[node.children[0]].clone();
-        let expr = self.expect_expr(child);
-        match expr {
-            Value::Bool(t) => {
-                if t {
-                     
-                } else {
-                    panic!("ASSERT FAILED")
+    fn handle_read(&mut self, index: usize) -> Result<(), RuntimeError> {
+        let node = &self.ast.arena[index];
+        let child = &self.ast.arena[node.children[0]];
+        let var_type = match &child.val {
+            AstItem::Variable(t) => &t.var_type,
+            AstItem::Index(_) => {
+                match &self.ast.arena[child.children[0]].val {
+                    AstItem::Variable(t) => {
+                        match &t.var_type {
+                            VariableType::Array(element_type, _) => element_type.as_ref(),
+                            _ => panic!("ERROR trying to index non-array variable {}", t.name)
+                        }
+                    }
+                    _ => panic!("ERROR trying to assign value to a non variable")
                 }
             }
-            _ => {
-                panic!("ERROR Assert expression is not evaluated into a bool");
+            _ => panic!("ERROR trying to assign value to a non variable")
+        };
+        let value = read_value(&mut self.input, var_type, self.integer_mode, source_info(node))?;
+        self.limits.check_string(&value, source_info(node))?;
+        let target = node.children[0];
+        match &self.ast.arena[target].val {
+            AstItem::Variable(t) => {
+                match self.variables.get_mut(&t.name) {
+                    Some(variable) => {
+                        variable.value = value;
+                        notify_write(self.hooks.get_mut(), &self.variables, &t.name, None, source_info(node));
+                        Ok(())
+                    }
+                    None => Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(t.name.clone()), t.source_info.clone()))
+                }
             }
+            _ => self.store_element(target, value)
         }
     }
 
-    fn handle_read(&mut self, node: Node<AstItem>) {
-        let child = self.ast.arena[node.children[0]].clone();
-        let var_name;
-        let var_type;
-        let var;
-        match child.val {
+    fn handle_assign(&mut self, index: usize) -> Result<(), RuntimeError> {
+        let node = &self.ast.arena[index];
+        let target = node.children[0];
+        let value = match node.children.get(1) {
+            Some(expr) => Some(self.expect_expr(*expr)?),
+            None => None
+        };
+        if let Some(t) = &value {
+            self.limits.check_string(t, source_info(node))?;
+        }
+        match &self.ast.arena[target].val {
             AstItem::Variable(t) => {
-                var_name = t.name;
-                var_type = t.var_type;
+                if let (None, VariableType::Array(_, size)) = (&value, &t.var_type) {
+                    self.limits.check_array(*size, source_info(node))?;
+                }
+                let value = value.unwrap_or_else(|| initial_value(&t.var_type));
+                match self.variables.get_mut(&t.name) {
+                    Some(variable) => {
+                        if variable.var_type != t.var_type {
+                            variable.var_type = t.var_type.clone();
+                        }
+                        variable.value = value;
+                    }
+                    None => {
+                        self.variables.insert(t.name.clone(), RunTimeVariable {name: t.name.clone(), var_type: t.var_type.clone(), value});
+                    }
+                }
+                notify_write(self.hooks.get_mut(), &self.variables, &t.name, None, source_info(node));
+                Ok(())
             }
+            AstItem::Index(_) => self.store_element(target, value.expect("ERROR element assignment without a value")),
             _ => panic!("ERROR trying to assign value to a non variable")
         }
-        let mut line = String::new();
-        std::io::stdin().read_line(&mut line).unwrap();
-        match var_type {
-            VariableType::Int => {
-                var = RunTimeVariable{name: var_name.clone(), var_type, value: Value::Int(line.trim().parse().unwrap())}
-            }
-            VariableType::String => {
-                trim_newline(&mut line);
-                var = RunTimeVariable{name: var_name.clone(), var_type, value: Value::String(line)}
-            }
-            VariableType::Bool => panic!("Cannot read a boolean value")
+    }
+
+    fn store_element(&mut self, index: usize, value: Value) -> Result<(), RuntimeError> {
+        let element = self.expect_index(index)?;
+        let var_name = array_name(&self.ast, index);
+        match self.variables.get_mut(var_name) {
+            Some(RunTimeVariable {value: Value::Array(elements), ..}) => elements[element] = value,
+            _ => panic!("ERROR trying to index non-array variable {}", var_name)
         }
-        if let std::collections::hash_map::Entry::Occupied(mut e) = self.variables.entry(var_name) {
-            e.insert(var);
-        } else {
-            panic!("Trying to read to an undefined variable")
+        notify_write(self.hooks.get_mut(), &self.variables, var_name, Some(element), source_info(&self.ast.arena[index]));
+        Ok(())
+    }
+
+    // Checks the indexed variable and the index, returns the element position
+    fn expect_index(&self, index: usize) -> Result<usize, RuntimeError> {
+        let node = &self.ast.arena[index];
+        let array_node = &self.ast.arena[node.children[0]];
+        let var_name = array_name(&self.ast, index);
+        match self.variables.get(var_name) {
+            Some(RunTimeVariable {value: Value::Array(_) | Value::String(_), ..}) => (),
+            Some(RunTimeVariable {value, ..}) => return Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: "array or string".to_string(), found: value.to_string()}, source_info(array_node).clone())),
+            None => return Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(var_name.to_string()), source_info(array_node).clone()))
         }
+        let element = self.expect_int(node.children[1])?;
+        check_index(var_name, &self.variables[var_name].value, element, source_info(node))
     }
 
-    fn handle_assign(&mut self, node: Node<AstItem>) {
-        let left_child = self.ast.arena[node.children[0]].clone();  
-        let var_name;
-        let var_type;
-        let var_value;
-        let var;
-        match left_child.val {
-            AstItem::Variable(t) => {
-                var_name = t.name;
-                var_type = t.var_type;
-            }
-            _ => panic!("ERROR trying to assign value to a non variable")
+    fn handle_index(&self, index: usize) -> Result<Value, RuntimeError> {
+        let element = self.expect_index(index)?;
+        Ok(element_at(&self.variables[array_name(&self.ast, index)].value, element))
+    }
+
+    fn handle_call(&self, index: usize) -> Result<Value, RuntimeError> {
+        let node = &self.ast.arena[index];
+        let call_info = match &node.val {
+            AstItem::Call(t) => t,
+            t => panic!("Error, unexpected call node {:#?}", t)
+        };
+        let mut args = Vec::with_capacity(node.children.len());
+        for child in &node.children {
+            args.push(self.expect_expr(*child)?);
         }
-        if node.children.len() > 1 {
-            let right_child = self.ast.arena[node.children[1]].clone();
-            var_value = self.expect_expr(right_child);
-            var = RunTimeVariable {name: var_name.clone(), var_type, value: var_value};    
-        } else {
-            var = RunTimeVariable {name: var_name.clone(), var_type, value: Value::NULL};
+        call_function(&call_info.name, &args, self.integer_mode, &self.natives, &call_info.source_info)
+    }
+
+    fn expect_int(&self, index: usize) -> Result<i32, RuntimeError> {
+        expect_int(self.expect_expr(index)?, source_info(&self.ast.arena[index]))
+    }
+
+    fn expect_expr(&self, index: usize) -> Result<Value, RuntimeError> {
+        let value = self.evaluate_expr(index)?;
+        if self.has_hooks {
+            for hook in self.hooks.borrow_mut().iter_mut() {
+                hook.expression_evaluated(&self.ast.arena[index].val, &value);
+            }
         }
-        self.variables.insert(var_name, var);
+        Ok(value)
     }
 
-    fn expect_expr(&self, node: Node<AstItem>) -> Value {
+    fn evaluate_expr(&self, index: usize) -> Result<Value, RuntimeError> {
+        let node = &self.ast.arena[index];
         if node.children.is_empty() {
-            return self.expect_opnd(node)
-        }
-        match node.val.clone() {
-            AstItem::BinOp(t) => {
-                match t {
-                    BinOpType::And => self.handle_and(node),
-                    BinOpType::Divide => self.handle_divide(node),
-                    BinOpType::Equal => self.handle_equal(node),
-                    BinOpType::LessThan => self.handle_less_than(node),
-                    BinOpType::Minus => self.handle_minus(node),
-                    BinOpType::Multiply => self.handle_multiply(node),  
-                    BinOpType::Plus => self.handle_plus(node),           
+            return self.expect_opnd(index)
+        }
+        match &node.val {
+            AstItem::BinOp(t, source_info) => {
+                let left = self.expect_expr(node.children[0])?;
+                if let Some(result) = short_circuit(t, &left) {
+                    return Ok(result);
                 }
+                let right = self.expect_expr(node.children[1])?;
+                binary_operation(t, left, right, self.integer_mode, source_info)
             }
+            AstItem::Index(_) => self.handle_index(index),
+            AstItem::Call(_) => self.handle_call(index),
+            AstItem::Not(_) | AstItem::Negate(_) => self.expect_opnd(index),
             _ => panic!("Error, unexpected node {:#?}", node)
         }
     }
-    
-    fn handle_plus(&self, node: Node<AstItem>) -> Value {
-        let left_child = self.ast.arena[node.children[0]].clone();
-        let right_child = self.ast.arena[node.children[1]].clone();
-        let left_side = self.expect_expr(left_child);
-        let right_side = self.expect_expr(right_child);
-        
-        match (left_side, right_side) {
-            (Value::Int(t), Value::Int(v)) => {
-                let sum = t + v;
-                Value::Int(sum)
-            }
-            (Value::String(t), Value::String(v)) => {
-                let mut concat = String::new();
-                concat.push_str(&t);
-                concat.push_str(&v);
-                Value::String(concat)
-            }
-            _ => panic!("Incomtabile add of two values")
-        }
-    }
-    fn handle_minus(&self, node: Node<AstItem>) -> Value {
-        let left_child = self.ast.arena[node.children[0]].clone();
-        let right_child = self.ast.arena[node.children[1]].clone();
-        let left_side = self.expect_expr(left_child);
-        let right_side = self.expect_expr(right_child);
-        
-        match (left_side, right_side) {
-            (Value::Int(t), Value::Int(v)) => {
-                let sub = t - v;
-                Value::Int(sub)
-            }
-            _ => panic!("Incomtabile substract of two values")
-        }
-    }
-    fn handle_multiply(&self, node: Node<AstItem>) -> Value {
-        let left_child = self.ast.arena[node.children[0]].clone();
-        let right_child = self.ast.arena[node.children[1]].clone();
-        let left_side = self.expect_expr(left_child);
-        let right_side = self.expect_expr(right_child);
-        
-        match (left_side, right_side) {
-            (Value::Int(t), Value::Int(v)) => {
-                let mul = t * v;
-                Value::Int(mul)
-            }
-            _ => panic!("Incomtabile multiply of two values")
-        }
-    }
-    fn handle_divide(&self, node: Node<AstItem>) -> Value {
-        let left_child = self.ast.arena[node.children[0]].clone();
-        let right_child = self.ast.arena[node.children[1]].clone();
-        let left_side = self.expect_expr(left_child);
-        let right_side = self.expect_expr(right_child);
-        
-        match (left_side, right_side) {
-            (Value::Int(t), Value::Int(v)) => {
-                let div = t / v;
-                Value::Int(div)
-            }
-            _ => panic!("Incomtabile division of two values")
-        }
-    }
-    fn handle_less_than(&self, node: Node<AstItem>) -> Value {
-        let left_child = self.ast.arena[node.children[0]].clone();
-        let right_child = self.ast.arena[node.children[1]].clone();
-        let left_side = self.expect_expr(left_child);
-        let right_side = self.expect_expr(right_child);
-        
-        match (left_side, right_side) {
-            (Value::Int(t), Value::Int(v)) => {
-                Value::Bool(t < v)
-            }
-            _ => panic!("Incomtabile comparison of two values")
-        }
-    }
-    fn handle_equal(&self, node: Node<AstItem>) -> Value {
-        let left_child = self.ast.arena[node.children[0]].clone();
-        let right_child = self.ast.arena[node.children[1]].clone();
-        let left_side = self.expect_expr(left_child);
-        let right_side = self.expect_expr(right_child);
-        
-        match (left_side, right_side) {
-            (Value::Int(t), Value::Int(v)) => {
-                Value::Bool(t == v)
-            }
-            (Value::String(t), Value::String(v)) => {
-                Value::Bool(t.eq(&v))
-            }
-            (Value::Bool(t), Value::Bool(v)) => {
-                Value::Bool(t == v)
-            }
-            _ => panic!("Incomtabile comparison of two values")
-        }
-    }
-    fn handle_and(&self, node: Node<AstItem>) -> Value {
-        let left_child = self.ast.arena[node.children[0]].clone();
-        let right_child = self.ast.arena[node.children[1]].clone();
-        let left_side = self.expect_expr(left_child);
-        let right_side = self.expect_expr(right_child);
-        
-        match (left_side, right_side) {
-            (Value::Bool(t), Value::Bool(v)) => {
-                Value::Bool(t && v)
-            }
-            _ => panic!("Incomtabile comparison of two values")
-        }
-    }
 
-    fn expect_opnd(&self, node: Node<AstItem>) -> Value {
+    fn expect_opnd(&self, index: usize) -> Result<Value, RuntimeError> {
+        let node = &self.ast.arena[index];
         match &node.val {
-            AstItem::Constant(t) => {
-                match t.const_type {
-                    VariableType::String => {
-                        Value::String(t.value.clone())
-                    }
-                    VariableType::Int => {
-                        Value::Int(t.value.clone().parse().unwrap())
-                    }
-                    VariableType::Bool => {
-                        let b_value;
-                        match t.value.as_str() {
-                            "true" => b_value = true,
-                            "false" => b_value = false,
-                            _ => panic!("UNEXPECTED ERROR bool value is not true or false")
-                        }
-                        Value::Bool(b_value)
-                    }
-                    
-                }
+            AstItem::Constant(t) => constant_value(t, self.integer_mode),
+            AstItem::Not(source_info) => {
+                not(self.expect_expr(node.children[0])?, source_info)
             }
-            AstItem::Not => {
-                let child_node = self.ast.arena[node.children[0]].clone();
-                let opnd = self.expect_opnd(child_node);
-                match opnd {
-                    Value::Bool(t) => {
-                        Value::Bool(!t)
-                    }
-
-                    _ => {
-                        panic!("ERROR trying to use logical not on non-boolean value");
-                    }
-                }
+            AstItem::Negate(source_info) => {
+                negate(self.expect_expr(node.children[0])?, self.integer_mode, source_info)
             }
+            AstItem::Call(_) => self.handle_call(index),
             AstItem::Variable(t) => {
                 let value = self.variables.get(&t.name);
                 match value {
-                    Some(t) => t.value.clone(),
-                    None => panic!("NULL Reference")
+                    Some(v) => Ok(v.value.clone()),
+                    None => Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(t.name.clone()), t.source_info.clone()))
                 }
             }
             _ => {
-                self.expect_expr(node)
+                self.expect_expr(index)
             }
         }
     }
 }
-fn trim_newline(s: &mut String) {
-    if s.ends_with('\n') {
-        s.pop();
-        if s.ends_with('\r') {
-            s.pop();
-        }
+
+fn notify_write(hooks: &mut [Box<dyn ExecutionHook>], variables: &HashMap<String, RunTimeVariable>, name: &str, element: Option<usize>, source_info: &SourceInfo) {
+    if hooks.is_empty() {
+        return;
+    }
+    let value = match (&variables[name].value, element) {
+        (Value::Array(elements), Some(i)) => &elements[i],
+        (t, _) => t
+    };
+    for hook in hooks.iter_mut() {
+        hook.variable_written(name, element, value, source_info);
     }
 }
 
-#[derive(Clone)]
-struct RunTimeVariable {
-    name: String,
-    var_type: VariableType,
-    value: Value
-
+fn array_name(ast: &ArenaTree<AstItem>, index: usize) -> &str {
+    let node = &ast.arena[index];
+    match &ast.arena[node.children[0]].val {
+        AstItem::Variable(v) => &v.name,
+        _ => panic!("Error, unexpected index node {:#?}", node.children)
+    }
 }
 
-#[derive(Clone)]
-enum Value {
-    Int(i32),
-    String(String),
-    Bool(bool),
-    NULL
+fn source_info(node: &Node<AstItem>) -> &SourceInfo {
+    node.val.source_info().expect("UNEXPECTED ERROR evaluated node has no source location")
 }
 
-impl fmt::Display for Value {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Value::Int(t) => write!(f, "{}", t),
-            Value::String(t) => write!(f, "{}", t),
-            Value::Bool(t) => write!(f, "{}", t),
-            Value::NULL => write!(f, "NULL")
-        }
-    }
-}
\ No newline at end of file
+pub struct RunTimeVariable {
+    pub name: String,
+    pub var_type: VariableType,
+    pub value: Value
+
+}
@@ -0,0 +1,96 @@
+use std::time::{Duration, Instant};
+
+use crate::language::ast::SourceInfo;
+use super::runtime_error::{RuntimeError, RuntimeErrorKind};
+use super::value::Value;
+
+// Caps for running untrusted programs, None means unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Limits {
+    pub max_steps: Option<u64>, // Executed statements and loop iterations
+    pub timeout: Option<Duration>,
+    pub max_output: Option<usize>, // Bytes printed
+    pub max_string: Option<usize>, // Characters in a stored string
+    pub max_array: Option<usize> // Elements in a declared array
+}
+
+// Counts what a running program has used so far against its Limits
+#[derive(Debug, Clone)]
+pub struct LimitTracker {
+    limits: Limits,
+    steps: u64,
+    printed: usize,
+    start: Instant
+}
+
+impl LimitTracker {
+
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            steps: 0,
+            printed: 0,
+            start: Instant::now()
+        }
+    }
+
+    // The timeout is measured from here
+    pub fn start(&mut self) {
+        self.start = Instant::now();
+    }
+
+    pub fn count_statement(&mut self, source_info: &SourceInfo) -> Result<(), RuntimeError> {
+        self.steps += 1;
+        if let Some(max) = self.limits.max_steps {
+            if self.steps > max {
+                return Err(RuntimeError::new(RuntimeErrorKind::StepLimitExceeded(max), source_info.clone()));
+            }
+        }
+        self.check_time(source_info)
+    }
+
+    pub fn check_time(&self, source_info: &SourceInfo) -> Result<(), RuntimeError> {
+        if let Some(timeout) = self.limits.timeout {
+            if self.start.elapsed() > timeout {
+                return Err(RuntimeError::new(RuntimeErrorKind::TimeLimitExceeded(timeout.as_millis()), source_info.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    // Called before value is printed, so nothing past the limit is written
+    pub fn count_output(&mut self, value: &Value, source_info: &SourceInfo) -> Result<(), RuntimeError> {
+        if let Some(max) = self.limits.max_output {
+            let length = match value {
+                Value::String(t) => t.len(),
+                t => t.to_string().len()
+            };
+            if self.printed + length > max {
+                return Err(RuntimeError::new(RuntimeErrorKind::OutputLimitExceeded(max), source_info.clone()));
+            }
+            self.printed += length;
+        }
+        Ok(())
+    }
+
+    // Called before a value is stored in a variable or an array element
+    pub fn check_string(&self, value: &Value, source_info: &SourceInfo) -> Result<(), RuntimeError> {
+        if let (Some(max), Value::String(t)) = (self.limits.max_string, value) {
+            // The byte length bounds the character count, so short strings are not counted
+            if t.len() > max && t.chars().count() > max {
+                return Err(RuntimeError::new(RuntimeErrorKind::StringLimitExceeded {length: t.chars().count(), limit: max}, source_info.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    // Called with the size of a declared array, before the tree walker allocates it
+    pub fn check_array(&self, size: usize, source_info: &SourceInfo) -> Result<(), RuntimeError> {
+        if let Some(max) = self.limits.max_array {
+            if size > max {
+                return Err(RuntimeError::new(RuntimeErrorKind::ArrayLimitExceeded {size, limit: max}, source_info.clone()));
+            }
+        }
+        Ok(())
+    }
+}
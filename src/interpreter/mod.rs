@@ -1 +1,12 @@
-pub mod interpreter;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod interpreter;
+pub mod runtime_error;
+pub mod streams;
+pub mod value;
+pub mod operations;
+pub mod arithmetic;
+pub mod builtins;
+pub mod natives;
+pub mod hooks;
+pub mod limits;
+pub mod snapshot;
\ No newline at end of file
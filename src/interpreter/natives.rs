@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::language::ast::{SourceInfo, VariableType};
+use crate::language::builtins::builtin_signature;
+use super::operations::has_type;
+use super::runtime_error::{RuntimeError, RuntimeErrorKind};
+use super::value::Value;
+
+// A host function receives arguments already checked against its parameter types and
+// returns its result or a message that becomes a runtime error at the call
+pub type NativeFunction = Rc<dyn Fn(&[Value]) -> Result<Value, String>>;
+
+#[derive(Clone)]
+struct Native {
+    parameters: Vec<VariableType>,
+    return_type: VariableType,
+    function: NativeFunction
+}
+
+// Functions the embedding application makes callable from Mini-PL. Clones share the
+// functions, so the type checker and the engine can each hold the registry.
+#[derive(Clone, Default)]
+pub struct NativeRegistry {
+    functions: HashMap<String, Native>
+}
+
+impl NativeRegistry {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, name: &str, parameters: Vec<VariableType>, return_type: VariableType, function: impl Fn(&[Value]) -> Result<Value, String> + 'static) -> Result<(), String> {
+        let mut chars = name.chars();
+        let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic()) && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!("ERROR function name {} is not an identifier", name));
+        }
+        if builtin_signature(name).is_some() {
+            return Err(format!("ERROR function {} is a built-in function", name));
+        }
+        if self.functions.contains_key(name) {
+            return Err(format!("ERROR function {} is already registered", name));
+        }
+        self.functions.insert(name.to_string(), Native {parameters, return_type, function: Rc::new(function)});
+        Ok(())
+    }
+
+    // Parameter types and return type, like language::builtins::builtin_signature
+    pub fn signature(&self, name: &str) -> Option<(Vec<VariableType>, VariableType)> {
+        self.functions.get(name).map(|t| (t.parameters.clone(), t.return_type.clone()))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    pub fn call(&self, name: &str, args: &[Value], source_info: &SourceInfo) -> Result<Value, RuntimeError> {
+        let native = match self.functions.get(name) {
+            Some(t) => t,
+            None => return Err(RuntimeError::new(RuntimeErrorKind::UndefinedFunction(name.to_string()), source_info.clone()))
+        };
+        let value = match (native.function)(args) {
+            Ok(t) => t,
+            Err(message) => return Err(RuntimeError::new(RuntimeErrorKind::NativeFunctionFailed {name: name.to_string(), message}, source_info.clone()))
+        };
+        // The checker trusts the declared return type, so a host function must keep to it
+        if !has_type(&value, &native.return_type) {
+            return Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: format!("{} from {}", native.return_type, name), found: value.to_string()}, source_info.clone()));
+        }
+        Ok(value)
+    }
+}
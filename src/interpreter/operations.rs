@@ -0,0 +1,246 @@
+use std::cmp::Ordering;
+use std::io::{BufRead, Write};
+
+use num_bigint::BigInt;
+
+use crate::data_structures::tree::ArenaTree;
+use crate::language::ast::{AstItem, BinOpType, ConstantInfo, SourceInfo, VariableType};
+use crate::language::source_text::operand_text;
+use super::arithmetic::{big_operation, int_negate, int_operation, int_value, parse_int, to_big, IntegerMode};
+use super::streams::read_token;
+use super::runtime_error::{RuntimeError, RuntimeErrorKind};
+use super::value::Value;
+
+// Value level semantics shared by the tree-walking interpreter and the bytecode VM
+
+pub fn constant_value(constant: &ConstantInfo, mode: IntegerMode) -> Result<Value, RuntimeError> {
+    match constant.const_type {
+        VariableType::String => {
+            Ok(Value::String(constant.value.clone()))
+        }
+        VariableType::Int => {
+            match parse_int(&constant.value, mode) {
+                Some(v) => Ok(v),
+                None => Err(RuntimeError::new(RuntimeErrorKind::InvalidConversion(constant.value.clone()), constant.source_info.clone()))
+            }
+        }
+        VariableType::Bool => {
+            match constant.value.as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => panic!("UNEXPECTED ERROR bool value is not true or false")
+            }
+        }
+        VariableType::Array(_, _) => panic!("UNEXPECTED ERROR array constant")
+    }
+}
+
+// Value of a declared but not yet assigned variable, the type default of the Mini-PL spec
+pub fn initial_value(var_type: &VariableType) -> Value {
+    match var_type {
+        VariableType::Int => Value::Int(0),
+        VariableType::String => Value::String(String::new()),
+        VariableType::Bool => Value::Bool(false),
+        VariableType::Array(element_type, size) => Value::Array(vec![initial_value(element_type); *size])
+    }
+}
+
+// Whether a value from outside the program, such as a host function result or a restored
+// variable, can be stored in a variable of the given type
+pub fn has_type(value: &Value, var_type: &VariableType) -> bool {
+    match (value, var_type) {
+        (Value::Int(_) | Value::Big(_), VariableType::Int) => true,
+        (Value::String(_), VariableType::String) => true,
+        (Value::Bool(_), VariableType::Bool) => true,
+        (Value::Array(elements), VariableType::Array(element_type, size)) => {
+            elements.len() == *size && elements.iter().all(|t| has_type(t, element_type))
+        }
+        _ => false
+    }
+}
+
+// The result of a logical operator when its left operand already decides it, in which case the
+// right operand must not be evaluated
+pub fn short_circuit(op: &BinOpType, left: &Value) -> Option<Value> {
+    match (op, left) {
+        (BinOpType::And, Value::Bool(false)) => Some(Value::Bool(false)),
+        (BinOpType::Or, Value::Bool(true)) => Some(Value::Bool(true)),
+        _ => None
+    }
+}
+
+pub fn binary_operation(op: &BinOpType, left: Value, right: Value, mode: IntegerMode, source_info: &SourceInfo) -> Result<Value, RuntimeError> {
+    match (op, left, right) {
+        (BinOpType::Plus | BinOpType::Minus | BinOpType::Multiply | BinOpType::Divide, Value::Int(t), Value::Int(v)) if mode != IntegerMode::Big => Ok(Value::Int(int_operation(op, t, v, mode, source_info)?)),
+        (BinOpType::Plus | BinOpType::Minus | BinOpType::Multiply | BinOpType::Divide, t @ (Value::Int(_) | Value::Big(_)), v @ (Value::Int(_) | Value::Big(_))) => {
+            big_operation(op, to_big(&t).unwrap(), to_big(&v).unwrap(), source_info)
+        }
+        (BinOpType::Plus, Value::String(t), Value::String(v)) => {
+            let mut concat = String::new();
+            concat.push_str(&t);
+            concat.push_str(&v);
+            Ok(Value::String(concat))
+        }
+        (BinOpType::And, Value::Bool(t), Value::Bool(v)) => Ok(Value::Bool(t && v)),
+        (BinOpType::Or, Value::Bool(t), Value::Bool(v)) => Ok(Value::Bool(t || v)),
+        (BinOpType::LessThan | BinOpType::GreaterThan | BinOpType::LessEqual | BinOpType::GreaterEqual | BinOpType::Equal | BinOpType::NotEqual, t, v) => {
+            // Strings compare lexicographically and false < true
+            let ordering = match (&t, &v) {
+                (Value::Int(t), Value::Int(v)) => t.cmp(v),
+                (Value::String(t), Value::String(v)) => t.cmp(v),
+                (Value::Bool(t), Value::Bool(v)) => t.cmp(v),
+                (Value::Int(_) | Value::Big(_), Value::Int(_) | Value::Big(_)) => to_big(&t).cmp(&to_big(&v)),
+                _ => return Err(incompatible_operands(op, t, v, source_info))
+            };
+            match op {
+                BinOpType::LessThan => Ok(Value::Bool(ordering == Ordering::Less)),
+                BinOpType::GreaterThan => Ok(Value::Bool(ordering == Ordering::Greater)),
+                BinOpType::LessEqual => Ok(Value::Bool(ordering != Ordering::Greater)),
+                BinOpType::GreaterEqual => Ok(Value::Bool(ordering != Ordering::Less)),
+                BinOpType::Equal => Ok(Value::Bool(ordering == Ordering::Equal)),
+                _ => Ok(Value::Bool(ordering != Ordering::Equal))
+            }
+        }
+        (op, t, v) => Err(incompatible_operands(op, t, v, source_info))
+    }
+}
+
+fn incompatible_operands(op: &BinOpType, left: Value, right: Value, source_info: &SourceInfo) -> RuntimeError {
+    RuntimeError::new(RuntimeErrorKind::IncompatibleOperands {operator: op.to_string(), left: left.to_string(), right: right.to_string()}, source_info.clone())
+}
+
+pub fn not(value: Value, source_info: &SourceInfo) -> Result<Value, RuntimeError> {
+    match value {
+        Value::Bool(t) => Ok(Value::Bool(!t)),
+        t => Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: "bool".to_string(), found: t.to_string()}, source_info.clone()))
+    }
+}
+
+pub fn negate(value: Value, mode: IntegerMode, source_info: &SourceInfo) -> Result<Value, RuntimeError> {
+    match value {
+        Value::Int(t) if mode == IntegerMode::Big => Ok(int_value(-BigInt::from(t))),
+        Value::Int(t) => Ok(Value::Int(int_negate(t, mode, source_info)?)),
+        Value::Big(t) => Ok(int_value(-t)),
+        t => Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: "int".to_string(), found: t.to_string()}, source_info.clone()))
+    }
+}
+
+pub fn expect_int(value: Value, source_info: &SourceInfo) -> Result<i32, RuntimeError> {
+    match value {
+        Value::Int(t) => Ok(t),
+        Value::Big(t) => Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: "int within the 32-bit range".to_string(), found: t.to_string()}, source_info.clone())),
+        t => Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: "int".to_string(), found: t.to_string()}, source_info.clone()))
+    }
+}
+
+pub fn check_assert(value: Value, source_info: &SourceInfo) -> Result<bool, RuntimeError> {
+    match value {
+        Value::Bool(t) => Ok(t),
+        t => Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: "bool".to_string(), found: t.to_string()}, source_info.clone()))
+    }
+}
+
+// The parts of an assert condition that are evaluated one by one so a failure can show their
+// values: the operands of an operator, or a bare variable itself. Other conditions are
+// evaluated whole.
+pub fn assert_operands(ast: &ArenaTree<AstItem>, condition: usize) -> Vec<usize> {
+    let node = &ast.arena[condition];
+    match &node.val {
+        AstItem::BinOp(_, _) | AstItem::Not(_) => node.children.clone(),
+        AstItem::Variable(_) => vec![condition],
+        _ => Vec::new()
+    }
+}
+
+// The value of a split assert condition. The right operand of & and | is None when the left one
+// decided the result and it was not evaluated.
+pub fn assert_condition(op: &BinOpType, left: &Value, right: Option<&Value>, mode: IntegerMode, source_info: &SourceInfo) -> Result<Value, RuntimeError> {
+    match (short_circuit(op, left), right) {
+        (Some(t), _) => Ok(t),
+        (None, Some(right)) => binary_operation(op, left.clone(), right.clone(), mode, source_info),
+        (None, None) => panic!("UNEXPECTED ERROR right operand of {} skipped", op)
+    }
+}
+
+// Constant operands are left out of the report, their value is already in the expression.
+// Operations are parenthesized so that "(x = 4) = true" cannot be misread.
+pub fn assert_operand_text(ast: &ArenaTree<AstItem>, operand: usize) -> Option<String> {
+    match &ast.arena[operand].val {
+        AstItem::Constant(_) => None,
+        _ => Some(operand_text(ast, operand))
+    }
+}
+
+// Operands without a value were skipped by short-circuit evaluation
+pub fn assertion_failed(expression: String, operands: Vec<(String, Option<&Value>)>, message: Option<Value>, source_info: &SourceInfo) -> RuntimeError {
+    let operands = operands.into_iter().map(|(text, value)| (text, value.map_or("not evaluated".to_string(), assert_value_text))).collect();
+    let message = message.map(|t| t.to_string());
+    RuntimeError::new(RuntimeErrorKind::AssertionFailed {expression, operands, message}, source_info.clone())
+}
+
+fn assert_value_text(value: &Value) -> String {
+    match value {
+        Value::String(t) => format!("{:?}", t),
+        t => t.to_string()
+    }
+}
+
+// Checks that index addresses an element of an array or a character of a string
+pub fn check_index(name: &str, container: &Value, index: i32, source_info: &SourceInfo) -> Result<usize, RuntimeError> {
+    let size = match container {
+        Value::Array(elements) => elements.len(),
+        Value::String(t) => t.chars().count(),
+        t => return Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: "array or string".to_string(), found: t.to_string()}, source_info.clone()))
+    };
+    if index >= 0 && (index as usize) < size {
+        Ok(index as usize)
+    } else {
+        Err(RuntimeError::new(RuntimeErrorKind::IndexOutOfBounds {name: name.to_string(), index, size}, source_info.clone()))
+    }
+}
+
+// Element of an array or character of a string at an index checked by check_index
+pub fn element_at(container: &Value, index: usize) -> Value {
+    match container {
+        Value::Array(elements) => elements[index].clone(),
+        Value::String(t) => Value::String(t.chars().nth(index).unwrap().to_string()),
+        t => panic!("ERROR trying to index non-array value {}", t)
+    }
+}
+
+pub fn print_value(output: &mut dyn Write, value: &Value, source_info: &SourceInfo) -> Result<(), RuntimeError> {
+    if let Value::Array(_) = value {
+        return Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: "printable value".to_string(), found: "array".to_string()}, source_info.clone()));
+    }
+    match write!(output, "{}", value).and_then(|_| output.flush()) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(RuntimeError::new(RuntimeErrorKind::Io(e.to_string()), source_info.clone()))
+    }
+}
+
+// Reads the next whitespace delimited word of the input, so several values can share a line
+pub fn read_value(input: &mut dyn BufRead, var_type: &VariableType, mode: IntegerMode, source_info: &SourceInfo) -> Result<Value, RuntimeError> {
+    let token = match read_token(input) {
+        Ok(Some(t)) => t,
+        Ok(None) => return Err(RuntimeError::new(RuntimeErrorKind::EndOfInput(var_type.clone()), source_info.clone())),
+        Err(e) => return Err(RuntimeError::new(RuntimeErrorKind::Io(e.to_string()), source_info.clone()))
+    };
+    let value = match var_type {
+        VariableType::Int => parse_int(&token, mode),
+        VariableType::String => Some(Value::String(token.clone())),
+        VariableType::Bool => {
+            match token.as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None
+            }
+        }
+        VariableType::Array(_, _) => {
+            return Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: "int, string or bool".to_string(), found: var_type.to_string()}, source_info.clone()))
+        }
+    };
+    match value {
+        Some(t) => Ok(t),
+        None => Err(RuntimeError::new(RuntimeErrorKind::InvalidInput {input: token, expected: var_type.clone()}, source_info.clone()))
+    }
+}
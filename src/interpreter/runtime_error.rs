@@ -0,0 +1,100 @@
+use core::fmt;
+use std::error::Error;
+
+use crate::language::ast::{SourceInfo, VariableType};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    AssertionFailed { expression: String, operands: Vec<(String, String)>, message: Option<String> },
+    UndefinedVariable(String),
+    TypeMismatch { expected: String, found: String },
+    IncompatibleOperands { operator: String, left: String, right: String },
+    ZeroStep,
+    IndexOutOfBounds { name: String, index: i32, size: usize },
+    SubstrOutOfBounds { start: i32, count: i32, length: usize },
+    InvalidConversion(String),
+    InvalidFormat(String),
+    DivisionByZero,
+    IntegerOverflow(String),
+    InvalidInput { input: String, expected: VariableType },
+    EndOfInput(VariableType),
+    Io(String),
+    DebuggerQuit,
+    StepLimitExceeded(u64),
+    TimeLimitExceeded(u128),
+    OutputLimitExceeded(usize),
+    StringLimitExceeded { length: usize, limit: usize },
+    ArrayLimitExceeded { size: usize, limit: usize },
+    UndefinedFunction(String),
+    NativeFunctionFailed { name: String, message: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub source_info: SourceInfo,
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind, source_info: SourceInfo) -> Self {
+        Self { kind, source_info }
+    }
+
+    // Process exit code, so that callers can tell exceeded limits apart from program errors
+    pub fn exit_code(&self) -> i32 {
+        match self.kind {
+            RuntimeErrorKind::StepLimitExceeded(_) => 3,
+            RuntimeErrorKind::TimeLimitExceeded(_) => 4,
+            RuntimeErrorKind::OutputLimitExceeded(_) => 5,
+            RuntimeErrorKind::StringLimitExceeded { .. } => 6,
+            RuntimeErrorKind::ArrayLimitExceeded { .. } => 7,
+            _ => 1
+        }
+    }
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::AssertionFailed { expression, operands, message } => {
+                write!(f, "assertion ({}) failed", expression)?;
+                let operands: Vec<String> = operands.iter().map(|(text, value)| format!("{} = {}", text, value)).collect();
+                match (message, operands.is_empty()) {
+                    (Some(t), true) => write!(f, ": {}", t),
+                    (Some(t), false) => write!(f, ": {} ({})", t, operands.join(", ")),
+                    (None, true) => Ok(()),
+                    (None, false) => write!(f, ": {}", operands.join(", "))
+                }
+            }
+            RuntimeErrorKind::UndefinedVariable(t) => write!(f, "undefined variable {}", t),
+            RuntimeErrorKind::TypeMismatch { expected, found } => write!(f, "expected {}, found {}", expected, found),
+            RuntimeErrorKind::IncompatibleOperands { operator, left, right } => write!(f, "incompatible operands for {}: {} and {}", operator, left, right),
+            RuntimeErrorKind::ZeroStep => write!(f, "range step must not be zero"),
+            RuntimeErrorKind::IndexOutOfBounds { name, index, size } => write!(f, "index {} out of bounds for {} of size {}", index, name, size),
+            RuntimeErrorKind::SubstrOutOfBounds { start, count, length } => write!(f, "substr({}, {}) out of bounds for string of length {}", start, count, length),
+            RuntimeErrorKind::InvalidConversion(t) => write!(f, "cannot convert \"{}\" to int", t),
+            RuntimeErrorKind::InvalidFormat(t) => write!(f, "cannot format: {}", t),
+            RuntimeErrorKind::DivisionByZero => write!(f, "division by zero"),
+            RuntimeErrorKind::IntegerOverflow(t) => write!(f, "integer overflow in {}", t),
+            RuntimeErrorKind::InvalidInput { input, expected } => write!(f, "invalid input \"{}\", expected {}", input, expected),
+            RuntimeErrorKind::EndOfInput(t) => write!(f, "end of input, expected {}", t),
+            RuntimeErrorKind::Io(t) => write!(f, "I/O failure: {}", t),
+            RuntimeErrorKind::DebuggerQuit => write!(f, "program stopped from the debugger"),
+            RuntimeErrorKind::StepLimitExceeded(t) => write!(f, "step limit of {} statements exceeded", t),
+            RuntimeErrorKind::TimeLimitExceeded(t) => write!(f, "time limit of {} ms exceeded", t),
+            RuntimeErrorKind::OutputLimitExceeded(t) => write!(f, "output limit of {} bytes exceeded", t),
+            RuntimeErrorKind::StringLimitExceeded { length, limit } => write!(f, "string of length {} exceeds the limit of {} characters", length, limit),
+            RuntimeErrorKind::ArrayLimitExceeded { size, limit } => write!(f, "array of {} elements exceeds the limit of {} elements", size, limit),
+            RuntimeErrorKind::UndefinedFunction(t) => write!(f, "undefined function {}", t),
+            RuntimeErrorKind::NativeFunctionFailed { name, message } => write!(f, "function {} failed: {}", name, message),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ERROR {}, {}", self.kind, self.source_info)
+    }
+}
+
+impl Error for RuntimeError {}
@@ -0,0 +1,201 @@
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::data_structures::tree::ArenaTree;
+use crate::language::ast::{AstItem, VariableType};
+use super::arithmetic::{parse_int, IntegerMode};
+use super::value::Value;
+
+// The interpreter's position in the program. Every block being executed has a frame, the
+// innermost one last, so the Rust call stack holds no state between statements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub block: usize, // Root or Block node
+    pub next: usize, // Position of the next statement among the block's children
+    pub for_loop: Option<LoopState>
+}
+
+// A running for statement whose body is the frame's block
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopState {
+    pub statement: usize,
+    pub counter: i64,
+    pub remaining: i64, // Iterations left, including the current one
+    pub step: i32
+}
+
+// Everything needed to resume a program: its variables and frames. Streams, limits and
+// hooks are not part of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub fingerprint: u64,
+    pub variables: Vec<(String, VariableType, Value)>,
+    pub frames: Vec<Frame>
+}
+
+const HEADER: &str = "minipl-snapshot 1";
+
+impl Snapshot {
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        match fs::write(path, self.to_text()) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("ERROR cannot write snapshot {}: {}", path.display(), e))
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(t) => Self::from_text(&t).map_err(|e| format!("ERROR invalid snapshot {}: {}", path.display(), e)),
+            Err(e) => Err(format!("ERROR cannot read snapshot {}: {}", path.display(), e))
+        }
+    }
+
+    // One tab separated record per line, values of an array follow its type
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\nfingerprint\t{:016x}\n", HEADER, self.fingerprint);
+        for (name, var_type, value) in &self.variables {
+            let mut fields = vec!["variable".to_string(), name.clone(), type_text(var_type)];
+            match value {
+                Value::Array(elements) => fields.extend(elements.iter().map(value_text)),
+                t => fields.push(value_text(t))
+            }
+            text.push_str(&fields.join("\t"));
+            text.push('\n');
+        }
+        for frame in &self.frames {
+            text.push_str(&format!("frame\t{}\t{}", frame.block, frame.next));
+            if let Some(t) = &frame.for_loop {
+                text.push_str(&format!("\t{}\t{}\t{}\t{}", t.statement, t.counter, t.remaining, t.step));
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("missing header".to_string());
+        }
+        let mut snapshot = Snapshot { fingerprint: 0, variables: Vec::new(), frames: Vec::new() };
+        for (number, line) in lines.enumerate() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let parsed = match fields[0] {
+                "fingerprint" if fields.len() == 2 => {
+                    u64::from_str_radix(fields[1], 16).ok().map(|t| snapshot.fingerprint = t)
+                }
+                "variable" if fields.len() >= 4 => {
+                    parse_type(fields[2]).and_then(|var_type| {
+                        let value = match &var_type {
+                            VariableType::Array(element_type, size) if fields.len() == 3 + size => {
+                                let elements: Option<Vec<Value>> = fields[3..].iter().map(|t| parse_value(t, element_type)).collect();
+                                Value::Array(elements?)
+                            }
+                            VariableType::Array(_, _) => return None,
+                            t if fields.len() == 4 => parse_value(fields[3], t)?,
+                            _ => return None
+                        };
+                        snapshot.variables.push((fields[1].to_string(), var_type, value));
+                        Some(())
+                    })
+                }
+                "frame" if fields.len() == 3 || fields.len() == 7 => {
+                    parse_frame(&fields).map(|t| snapshot.frames.push(t))
+                }
+                _ => None
+            };
+            if parsed.is_none() {
+                return Err(format!("unexpected record on line {}", number + 2));
+            }
+        }
+        Ok(snapshot)
+    }
+}
+
+// Identifies the program a snapshot belongs to, FNV-1a over the nodes of its tree
+pub fn fingerprint(ast: &ArenaTree<AstItem>) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for node in &ast.arena {
+        // The same program may be named by another path when it is restored
+        let mut item = node.val.clone();
+        if let Some(t) = item.source_info_mut() {
+            t.file = Rc::from("");
+        }
+        let text = format!("{:?}{:?}", item, node.children);
+        for byte in text.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+fn type_text(var_type: &VariableType) -> String {
+    match var_type {
+        VariableType::Array(t, size) => format!("array[{}]:{}", size, type_text(t)),
+        t => t.to_string()
+    }
+}
+
+fn parse_type(text: &str) -> Option<VariableType> {
+    match text {
+        "int" => Some(VariableType::Int),
+        "string" => Some(VariableType::String),
+        "bool" => Some(VariableType::Bool),
+        t => {
+            let (size, element_type) = t.strip_prefix("array[")?.split_once("]:")?;
+            Some(VariableType::Array(Box::new(parse_type(element_type)?), size.parse().ok()?))
+        }
+    }
+}
+
+// Strings escape the characters that would break the record
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(t) => t.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r"),
+        t => t.to_string()
+    }
+}
+
+fn parse_value(text: &str, var_type: &VariableType) -> Option<Value> {
+    match var_type {
+        // Values beyond 32 bits only come from big integer mode, and stay big when restored
+        VariableType::Int => parse_int(text, IntegerMode::Big),
+        VariableType::Bool => text.parse().ok().map(Value::Bool),
+        VariableType::String => {
+            let mut unescaped = String::new();
+            let mut chars = text.chars();
+            while let Some(c) = chars.next() {
+                if c != '\\' {
+                    unescaped.push(c);
+                    continue;
+                }
+                match chars.next()? {
+                    't' => unescaped.push('\t'),
+                    'n' => unescaped.push('\n'),
+                    'r' => unescaped.push('\r'),
+                    '\\' => unescaped.push('\\'),
+                    _ => return None
+                }
+            }
+            Some(Value::String(unescaped))
+        }
+        VariableType::Array(_, _) => None
+    }
+}
+
+fn parse_frame(fields: &[&str]) -> Option<Frame> {
+    let for_loop = if fields.len() == 7 {
+        Some(LoopState {
+            statement: fields[3].parse().ok()?,
+            counter: fields[4].parse().ok()?,
+            remaining: fields[5].parse().ok()?,
+            step: fields[6].parse().ok()?
+        })
+    } else {
+        None
+    };
+    Some(Frame { block: fields[1].parse().ok()?, next: fields[2].parse().ok()?, for_loop })
+}
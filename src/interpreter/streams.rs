@@ -0,0 +1,98 @@
+use std::cell::RefCell;
+use std::io::{self, BufRead, Read, Write};
+use std::rc::Rc;
+
+// In-memory output sink. Clones share the same buffer, so a caller can keep one handle and give
+// the other to the interpreter.
+#[derive(Clone, Default)]
+pub struct OutputBuffer {
+    buffer: Rc<RefCell<Vec<u8>>>,
+}
+
+impl OutputBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.buffer.borrow()).into_owned()
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Reads stdin one line at a time and holds its lock only while reading, so a line editor
+// can read the lines in between
+#[derive(Default)]
+pub struct LineStdin {
+    line: Vec<u8>,
+    position: usize
+}
+
+impl Read for LineStdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.consume(count);
+        Ok(count)
+    }
+}
+
+impl BufRead for LineStdin {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.position >= self.line.len() {
+            self.line.clear();
+            self.position = 0;
+            io::stdin().lock().read_until(b'\n', &mut self.line)?;
+        }
+        Ok(&self.line[self.position..])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.position += amount;
+    }
+}
+
+// Skips leading whitespace and returns the following run of non-whitespace characters, or None
+// at the end of the input. The whitespace after the word is left unread.
+pub fn read_token(input: &mut dyn BufRead) -> io::Result<Option<String>> {
+    let mut token = Vec::new();
+    loop {
+        let buffer = input.fill_buf()?;
+        if buffer.is_empty() {
+            break;
+        }
+        let mut used = 0;
+        let mut complete = false;
+        for byte in buffer {
+            if byte.is_ascii_whitespace() {
+                if !token.is_empty() {
+                    complete = true;
+                    break;
+                }
+            } else {
+                token.push(*byte);
+            }
+            used += 1;
+        }
+        input.consume(used);
+        if complete {
+            break;
+        }
+    }
+    if token.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(String::from_utf8_lossy(&token).into_owned()))
+    }
+}
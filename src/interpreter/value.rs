@@ -0,0 +1,27 @@
+use core::fmt;
+
+use num_bigint::BigInt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Big(BigInt), // Only in big integer mode, for ints outside the i32 range
+    String(String),
+    Bool(bool),
+    Array(Vec<Value>)
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(t) => write!(f, "{}", t),
+            Value::Big(t) => write!(f, "{}", t),
+            Value::String(t) => write!(f, "{}", t),
+            Value::Bool(t) => write!(f, "{}", t),
+            Value::Array(t) => {
+                let elements: Vec<String> = t.iter().map(|e| e.to_string()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+        }
+    }
+}
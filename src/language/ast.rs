@@ -1,23 +1,68 @@
+use std::fmt;
+use std::rc::Rc;
 
-#[derive(Debug, PartialEq, Clone)]
+
+#[derive(Debug, PartialEq, Clone, Default)]
 pub enum AstItem {
-    Assign,
+    Assign(SourceInfo),
     Variable(VariableInfo),
+    Index(SourceInfo),
+    Call(CallInfo),
     Constant(ConstantInfo),
-    Not,
-    BinOp(BinOpType),
-    Read,
-    Print,
-    Assert,
+    Not(SourceInfo),
+    Negate(SourceInfo),
+    BinOp(BinOpType, SourceInfo),
+    Read(SourceInfo),
+    Print(SourceInfo),
+    Println(SourceInfo),
+    Assert(SourceInfo),
     Block,
-    For,
+    For(SourceInfo),
     Range,
 
     Root,
+    #[default]
     Default
 }
-impl Default for AstItem {
-    fn default() -> Self {AstItem::Default}
+
+impl AstItem {
+    pub fn source_info(&self) -> Option<&SourceInfo> {
+        match self {
+            AstItem::Variable(t) => Some(&t.source_info),
+            AstItem::Constant(t) => Some(&t.source_info),
+            AstItem::Call(t) => Some(&t.source_info),
+            AstItem::Assign(t)
+            | AstItem::Index(t)
+            | AstItem::Not(t)
+            | AstItem::Negate(t)
+            | AstItem::BinOp(_, t)
+            | AstItem::Read(t)
+            | AstItem::Print(t)
+            | AstItem::Println(t)
+            | AstItem::Assert(t)
+            | AstItem::For(t) => Some(t),
+            AstItem::Block | AstItem::Range | AstItem::Root | AstItem::Default => None
+        }
+    }
+
+    pub fn source_info_mut(&mut self) -> Option<&mut SourceInfo> {
+        match self {
+            AstItem::Variable(t) => Some(&mut t.source_info),
+            AstItem::Constant(t) => Some(&mut t.source_info),
+            AstItem::Call(t) => Some(&mut t.source_info),
+            AstItem::Assign(t)
+            | AstItem::Index(t)
+            | AstItem::Not(t)
+            | AstItem::Negate(t)
+            | AstItem::BinOp(_, t)
+            | AstItem::Read(t)
+            | AstItem::Print(t)
+            | AstItem::Println(t)
+            | AstItem::Assert(t)
+            | AstItem::For(t) => Some(t),
+            AstItem::Block | AstItem::Range | AstItem::Root | AstItem::Default => None
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -27,6 +72,12 @@ pub struct VariableInfo {
     pub source_info: SourceInfo
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub struct CallInfo {
+    pub name: String,
+    pub source_info: SourceInfo
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum  BinOpType {
     Plus,
@@ -34,8 +85,32 @@ pub enum  BinOpType {
     Multiply,
     Divide,
     LessThan,
+    GreaterThan,
+    LessEqual,
+    GreaterEqual,
     Equal,
-    And
+    NotEqual,
+    And,
+    Or
+}
+
+impl fmt::Display for BinOpType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinOpType::Plus => write!(f, "+"),
+            BinOpType::Minus => write!(f, "-"),
+            BinOpType::Multiply => write!(f, "*"),
+            BinOpType::Divide => write!(f, "/"),
+            BinOpType::LessThan => write!(f, "<"),
+            BinOpType::GreaterThan => write!(f, ">"),
+            BinOpType::LessEqual => write!(f, "<="),
+            BinOpType::GreaterEqual => write!(f, ">="),
+            BinOpType::Equal => write!(f, "="),
+            BinOpType::NotEqual => write!(f, "<>"),
+            BinOpType::And => write!(f, "&"),
+            BinOpType::Or => write!(f, "|")
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -49,11 +124,34 @@ pub struct  ConstantInfo {
 pub enum VariableType {
     String,
     Int,
-    Bool
+    Bool,
+    Array(Box<VariableType>, usize)
 }
 
-#[derive(Debug, PartialEq, Clone)]
+impl fmt::Display for VariableType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariableType::String => write!(f, "string"),
+            VariableType::Int => write!(f, "int"),
+            VariableType::Bool => write!(f, "bool"),
+            VariableType::Array(t, size) => write!(f, "array[{}] of {}", size, t)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct  SourceInfo {
     pub line: i32,
-    pub column: i32
+    pub column: i32,
+    pub file: Rc<str>
+}
+
+impl fmt::Display for SourceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.file.is_empty() {
+            write!(f, "line {}, column {}", self.line, self.column)
+        } else {
+            write!(f, "{}, line {}, column {}", self.file, self.line, self.column)
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,21 @@
+use super::ast::VariableType;
+
+// Parameter types and return type of each built-in function
+pub fn builtin_signature(name: &str) -> Option<(Vec<VariableType>, VariableType)> {
+    match name {
+        "len" => Some((vec![VariableType::String], VariableType::Int)),
+        "substr" => Some((vec![VariableType::String, VariableType::Int, VariableType::Int], VariableType::String)),
+        "indexOf" => Some((vec![VariableType::String, VariableType::String], VariableType::Int)),
+        "upper" => Some((vec![VariableType::String], VariableType::String)),
+        "lower" => Some((vec![VariableType::String], VariableType::String)),
+        "toString" => Some((vec![VariableType::Int], VariableType::String)),
+        "toInt" => Some((vec![VariableType::String], VariableType::Int)),
+        "format" => Some((vec![VariableType::String], VariableType::String)),
+        _ => None
+    }
+}
+
+// Built-ins that take any number of int, string or bool values after their parameters
+pub fn builtin_variadic(name: &str) -> bool {
+    name == "format"
+}
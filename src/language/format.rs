@@ -0,0 +1,103 @@
+use super::ast::VariableType;
+
+// Format strings of the format built-in. A field is '%' [flag] [width] conversion, where the flag
+// is '-' for left alignment or '0' for zero padding and the conversion is 'd' for int, 's' for
+// string or 'b' for bool. Fields are right aligned, "%%" is a literal percent sign.
+#[derive(Debug, PartialEq, Clone)]
+pub enum FormatPart {
+    Text(String),
+    Field(FormatField)
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FormatField {
+    pub left: bool,
+    pub zero: bool,
+    pub width: usize,
+    pub value_type: VariableType
+}
+
+// Keeps a single field from allocating an arbitrarily long string
+pub const MAX_WIDTH: usize = 1000;
+
+pub fn parse_format(format: &str) -> Result<Vec<FormatPart>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+        if chars.next_if_eq(&'%').is_some() {
+            text.push('%');
+            continue;
+        }
+        let left = chars.next_if_eq(&'-').is_some();
+        let zero = !left && chars.next_if_eq(&'0').is_some();
+        let mut digits = String::new();
+        while let Some(digit) = chars.next_if(|t| t.is_ascii_digit()) {
+            digits.push(digit);
+        }
+        let width = match digits.parse::<usize>() {
+            Ok(t) if t > MAX_WIDTH => return Err(format!("width {} is larger than {}", digits, MAX_WIDTH)),
+            Ok(t) => t,
+            Err(_) if digits.is_empty() => 0,
+            Err(_) => return Err(format!("width {} is larger than {}", digits, MAX_WIDTH))
+        };
+        let value_type = match chars.next() {
+            Some('d') => VariableType::Int,
+            Some('s') => VariableType::String,
+            Some('b') => VariableType::Bool,
+            Some(t) => return Err(format!("unknown conversion %{}", t)),
+            None => return Err("unfinished field at the end".to_string())
+        };
+        if zero && value_type != VariableType::Int {
+            return Err(format!("zero padding needs %d, found %{}", conversion(&value_type)));
+        }
+        if !text.is_empty() {
+            parts.push(FormatPart::Text(std::mem::take(&mut text)));
+        }
+        parts.push(FormatPart::Field(FormatField {left, zero, width, value_type}));
+    }
+    if !text.is_empty() {
+        parts.push(FormatPart::Text(text));
+    }
+    Ok(parts)
+}
+
+pub fn fields(parts: &[FormatPart]) -> Vec<&FormatField> {
+    parts.iter().filter_map(|t| match t {
+        FormatPart::Field(field) => Some(field),
+        FormatPart::Text(_) => None
+    }).collect()
+}
+
+impl FormatField {
+    // Fills text up to the field width, zeros go after the sign of a negative number
+    pub fn pad(&self, text: &str) -> String {
+        let length = text.chars().count();
+        if length >= self.width {
+            return text.to_string();
+        }
+        let fill = self.width - length;
+        if self.left {
+            format!("{}{}", text, " ".repeat(fill))
+        } else if self.zero {
+            match text.strip_prefix('-') {
+                Some(digits) => format!("-{}{}", "0".repeat(fill), digits),
+                None => format!("{}{}", "0".repeat(fill), text)
+            }
+        } else {
+            format!("{}{}", " ".repeat(fill), text)
+        }
+    }
+}
+
+fn conversion(value_type: &VariableType) -> char {
+    match value_type {
+        VariableType::Int => 'd',
+        VariableType::Bool => 'b',
+        _ => 's'
+    }
+}
@@ -1,16 +1,23 @@
+use std::rc::Rc;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum LexItem {
     //One character tokens
     LeftParen(LexItemInfo),
     RightParen(LexItemInfo),
+    LeftBracket(LexItemInfo),
+    RightBracket(LexItemInfo),
     Plus(LexItemInfo),
     Minus(LexItemInfo),
     Slash(LexItemInfo),
     Star(LexItemInfo),
     LessThan(LexItemInfo),
+    GreaterThan(LexItemInfo),
     And(LexItemInfo),
+    Or(LexItemInfo),
     Not(LexItemInfo),
     StatementEnd(LexItemInfo),
+    Comma(LexItemInfo),
     Equal(LexItemInfo),
     // One or Two character tokens
     Separator(LexItemInfo),
@@ -18,6 +25,9 @@ pub enum LexItem {
     //Two character tplem
     Range(LexItemInfo),
     Assign(LexItemInfo),
+    LessEqual(LexItemInfo),
+    GreaterEqual(LexItemInfo),
+    NotEqual(LexItemInfo),
 
     //Literals
     StringLiteral(LexItemInfo),
@@ -31,12 +41,17 @@ pub enum LexItem {
     End(LexItemInfo),
     In(LexItemInfo),
     Do(LexItemInfo),
+    Step(LexItemInfo),
     Read(LexItemInfo),
     Print(LexItemInfo),
+    Println(LexItemInfo),
     Int(LexItemInfo),
     String(LexItemInfo),
     Bool(LexItemInfo),
     Assert(LexItemInfo),
+    Include(LexItemInfo),
+    Array(LexItemInfo),
+    Of(LexItemInfo),
 
     Identifier(LexItemInfo),
 
@@ -47,8 +62,60 @@ pub struct LexItemInfo {
     pub text: String,
     pub line_number: i32,
     pub column_number: i32,
+    pub file: Rc<str>,
 }
 
 impl Default for LexItem {
-    fn default() -> Self {LexItem::Default(LexItemInfo {text: "default".into(), line_number: -1, column_number: -1})}
+    fn default() -> Self {LexItem::Default(LexItemInfo {text: "default".into(), line_number: -1, column_number: -1, file: "".into()})}
+}
+
+impl LexItem {
+    pub fn info(&self) -> &LexItemInfo {
+        match self {
+            LexItem::LeftParen(t)
+            | LexItem::RightParen(t)
+            | LexItem::LeftBracket(t)
+            | LexItem::RightBracket(t)
+            | LexItem::Plus(t)
+            | LexItem::Minus(t)
+            | LexItem::Slash(t)
+            | LexItem::Star(t)
+            | LexItem::LessThan(t)
+            | LexItem::GreaterThan(t)
+            | LexItem::And(t)
+            | LexItem::Or(t)
+            | LexItem::Not(t)
+            | LexItem::StatementEnd(t)
+            | LexItem::Comma(t)
+            | LexItem::Equal(t)
+            | LexItem::Separator(t)
+            | LexItem::Range(t)
+            | LexItem::Assign(t)
+            | LexItem::LessEqual(t)
+            | LexItem::GreaterEqual(t)
+            | LexItem::NotEqual(t)
+            | LexItem::StringLiteral(t)
+            | LexItem::IntegerLiteral(t)
+            | LexItem::BoolTrue(t)
+            | LexItem::BoolFalse(t)
+            | LexItem::Var(t)
+            | LexItem::For(t)
+            | LexItem::End(t)
+            | LexItem::In(t)
+            | LexItem::Do(t)
+            | LexItem::Step(t)
+            | LexItem::Read(t)
+            | LexItem::Print(t)
+            | LexItem::Println(t)
+            | LexItem::Int(t)
+            | LexItem::String(t)
+            | LexItem::Bool(t)
+            | LexItem::Assert(t)
+            | LexItem::Include(t)
+            | LexItem::Array(t)
+            | LexItem::Of(t)
+            | LexItem::Identifier(t)
+            | LexItem::Default(t) => t
+        }
+    }
 }
\ No newline at end of file
@@ -1,2 +1,5 @@
 pub mod lex;
-pub mod ast;
\ No newline at end of file
+pub mod ast;
+pub mod builtins;
+pub mod source_text;
+pub mod format;
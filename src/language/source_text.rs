@@ -0,0 +1,32 @@
+use crate::data_structures::tree::ArenaTree;
+use super::ast::{AstItem, VariableType};
+
+// Writes an expression back as Mini-PL source. The parser drops parentheses, so they are put
+// around every nested binary or unary operation, which the grammar requires anyway.
+pub fn expression_text(ast: &ArenaTree<AstItem>, index: usize) -> String {
+    let node = &ast.arena[index];
+    match &node.val {
+        AstItem::Variable(t) => t.name.clone(),
+        AstItem::Constant(t) => match t.const_type {
+            VariableType::String => format!("{:?}", t.value),
+            _ => t.value.clone()
+        },
+        AstItem::Index(_) => format!("{}[{}]", expression_text(ast, node.children[0]), expression_text(ast, node.children[1])),
+        AstItem::Call(t) => {
+            let args: Vec<String> = node.children.iter().map(|child| expression_text(ast, *child)).collect();
+            format!("{}({})", t.name, args.join(", "))
+        }
+        AstItem::Not(_) => format!("!{}", operand_text(ast, node.children[0])),
+        AstItem::Negate(_) => format!("-{}", operand_text(ast, node.children[0])),
+        AstItem::BinOp(op, _) => format!("{} {} {}", operand_text(ast, node.children[0]), op, operand_text(ast, node.children[1])),
+        t => panic!("Unexpected expression node {:#?}", t)
+    }
+}
+
+// An expression written where it is read as one operand, such as beside an operator
+pub fn operand_text(ast: &ArenaTree<AstItem>, index: usize) -> String {
+    match ast.arena[index].val {
+        AstItem::BinOp(_, _) | AstItem::Not(_) | AstItem::Negate(_) => format!("({})", expression_text(ast, index)),
+        _ => expression_text(ast, index)
+    }
+}
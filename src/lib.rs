@@ -0,0 +1,38 @@
+//! Interpreter for Mini-PL, a small teaching language with integers, strings, booleans,
+//! fixed-size arrays, `for` loops, `read`, `print`, `println` and `assert`.
+//!
+//! [`Engine`] runs a program from source text or a file. The scanner, parser, AST, checkers
+//! and both execution backends are public modules for tools that need to work on the
+//! individual stages.
+//!
+//! # Evaluation order
+//!
+//! Expressions are evaluated left to right: the left operand of a binary operator, then the
+//! right one, function arguments in order, and the index of `a[i]` after checking `a`. The
+//! logical operators short-circuit, `&` skips its right operand when the left one is false and
+//! `|` when it is true, so the left side can guard the right:
+//!
+//! ```text
+//! assert((i < n) & (a[i] = 0));
+//! ```
+//!
+//! `print` and `println` evaluate all their expressions before writing any of them.
+//!
+//! Both backends follow this order, so output and the first runtime error are the same.
+pub mod language;
+pub mod parser;
+pub mod interpreter;
+pub mod data_structures;
+pub mod checker;
+pub mod debugger;
+pub mod tracer;
+pub mod profiler;
+pub mod coverage;
+pub mod repl;
+pub mod loader;
+pub mod vm;
+pub mod engine;
+
+pub mod scanner;
+
+pub use engine::engine::{Backend, Engine, Error, Outcome};
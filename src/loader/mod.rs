@@ -0,0 +1 @@
+pub mod source_loader;
\ No newline at end of file
@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::language::lex::{LexItem, LexItemInfo};
+use crate::scanner::lexical_scanner::Scanner;
+
+// Resolves `include "path";` statements by splicing the tokens of the included file in place
+// of the statement. Paths are relative to the including file. Every file is scanned once and
+// spliced into the program only at its first include, so shared helpers can be included from
+// several files without redefining their variables.
+#[derive(Clone, Default)]
+pub struct SourceLoader {
+    included: HashSet<PathBuf>,
+}
+
+impl SourceLoader {
+
+    pub fn new() -> Self {
+        Self {
+            included: HashSet::new(),
+        }
+    }
+
+    pub fn load(&mut self, path: &Path) -> Result<Vec<LexItem>, String> {
+        let mut include_stack = Vec::new();
+        self.load_file(path, None, &mut include_stack)
+    }
+
+    // Loads a program that is not read from a file, its includes are relative to the
+    // current directory
+    pub fn load_source(&mut self, program: String, name: &str) -> Result<Vec<LexItem>, String> {
+        let tokens = Scanner::new(program, name).scan()?;
+        let mut include_stack = Vec::new();
+        self.splice_includes(tokens, Path::new(""), &mut include_stack)
+    }
+
+    // Errors about an included file point at the include statement that named it
+    fn load_file(&mut self, path: &Path, site: Option<&LexItemInfo>, include_stack: &mut Vec<PathBuf>) -> Result<Vec<LexItem>, String> {
+        let location = site.map_or(String::new(), |t| format!(", {}, line {}, column {}", t.file, t.line_number, t.column_number));
+        let canonical = match fs::canonicalize(path) {
+            Ok(t) => t,
+            Err(e) => return Err(format!("ERROR cannot open {}: {}{}", path.display(), e, location))
+        };
+        if include_stack.contains(&canonical) {
+            let mut cycle: Vec<String> = include_stack.iter().map(|t| t.display().to_string()).collect();
+            cycle.push(canonical.display().to_string());
+            return Err(format!("ERROR include cycle: {}{}", cycle.join(" -> "), location));
+        }
+        if !self.included.insert(canonical.clone()) {
+            return Ok(Vec::new());
+        }
+        let program = match fs::read_to_string(&canonical) {
+            Ok(t) => t,
+            Err(e) => return Err(format!("ERROR cannot read {}: {}{}", path.display(), e, location))
+        };
+        let tokens = Scanner::new(program, &path.display().to_string()).scan()?;
+
+        include_stack.push(canonical.clone());
+        let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let result = self.splice_includes(tokens, &directory, include_stack)?;
+        include_stack.pop();
+        Ok(result)
+    }
+
+    fn splice_includes(&mut self, tokens: Vec<LexItem>, directory: &Path, include_stack: &mut Vec<PathBuf>) -> Result<Vec<LexItem>, String> {
+        let mut result = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            if let LexItem::Include(t) = &tokens[i] {
+                match (tokens.get(i + 1), tokens.get(i + 2)) {
+                    (Some(LexItem::StringLiteral(p)), Some(LexItem::StatementEnd(_))) => {
+                        let included = self.load_file(&directory.join(&p.text), Some(t), include_stack)?;
+                        result.extend(included);
+                        i += 3;
+                        continue;
+                    }
+                    _ => return Err(format!("ERROR expected file name and ';' after include, {}, line {}, column {}", t.file, t.line_number, t.column_number))
+                }
+            }
+            result.push(tokens[i].clone());
+            i += 1;
+        }
+        Ok(result)
+    }
+}
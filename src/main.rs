@@ -1,28 +1,277 @@
-use interpreter::interpreter::Interpreter;
-use parser::syntax_parser::SyntaxParser;
-use scanner::lexical_scanner::Scanner;
+use minipl_interpreter::{Backend, Engine};
+use minipl_interpreter::debugger::debugger::Debugger;
+use minipl_interpreter::tracer::tracer::{TraceFormat, Tracer};
+use minipl_interpreter::profiler::profiler::{ProfileFormat, Profiler};
+use minipl_interpreter::coverage::coverage::Coverage;
+use minipl_interpreter::repl::repl::Repl;
+use minipl_interpreter::interpreter::arithmetic::IntegerMode;
+use minipl_interpreter::interpreter::interpreter::Interpreter;
+use minipl_interpreter::interpreter::limits::Limits;
+use minipl_interpreter::interpreter::snapshot::Snapshot;
+use minipl_interpreter::language::ast::AstItem;
 use std::env;
-use std::fs;
-mod language;
-mod parser;
-mod interpreter;
-mod data_structures;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+// Options that take the next argument as their value
+const VALUE_OPTIONS: [&str; 16] = ["--engine", "--integers", "--trace-format", "--trace-file", "--profile-format", "--profile-file",
+    "--coverage", "--coverage-merge", "--snapshot", "--snapshot-every", "--restore", "--max-steps", "--timeout", "--max-output",
+    "--max-string", "--max-array"];
 
-mod scanner;
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let filename = &args[1];
-    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
-    let scanner = Scanner::new(contents);
-    
+    let mut filename = None;
+    let mut backend = "tree";
+    let mut integers = "checked";
+    let mut debug = false;
+    let mut repl = false;
+    let mut strict = false;
+    let mut trace = false;
+    let mut trace_format = "text";
+    let mut trace_file = None;
+    let mut profile = false;
+    let mut profile_format = "text";
+    let mut profile_file = None;
+    let mut coverage_file = None;
+    let mut coverage_merge = false;
+    let mut snapshot_file = None;
+    let mut snapshot_every = None;
+    let mut snapshot_at_read = false;
+    let mut restore_file = None;
+    let mut limits = Limits::default();
+    let mut i = 1;
+    if args.len() > 1 && args[1] == "debug" {
+        debug = true;
+        i = 2;
+    } else if args.len() > 1 && args[1] == "repl" {
+        repl = true;
+        i = 2;
+    }
+    while i < args.len() {
+        match args[i].as_str() {
+            "--engine" if i + 1 < args.len() => {
+                backend = &args[i + 1];
+                i += 1;
+            }
+            "--integers" if i + 1 < args.len() => {
+                integers = &args[i + 1];
+                i += 1;
+            }
+            "--strict" => strict = true,
+            "--trace" => trace = true,
+            "--trace-format" if i + 1 < args.len() => {
+                trace = true;
+                trace_format = &args[i + 1];
+                i += 1;
+            }
+            "--trace-file" if i + 1 < args.len() => {
+                trace = true;
+                trace_file = Some(&args[i + 1]);
+                i += 1;
+            }
+            "--profile" => profile = true,
+            "--profile-format" if i + 1 < args.len() => {
+                profile = true;
+                profile_format = &args[i + 1];
+                i += 1;
+            }
+            "--profile-file" if i + 1 < args.len() => {
+                profile = true;
+                profile_file = Some(&args[i + 1]);
+                i += 1;
+            }
+            "--coverage" if i + 1 < args.len() => {
+                coverage_file = Some(&args[i + 1]);
+                i += 1;
+            }
+            "--coverage-merge" if i + 1 < args.len() => {
+                coverage_file = Some(&args[i + 1]);
+                coverage_merge = true;
+                i += 1;
+            }
+            "--snapshot" if i + 1 < args.len() => {
+                snapshot_file = Some(&args[i + 1]);
+                i += 1;
+            }
+            "--snapshot-every" if i + 1 < args.len() => {
+                snapshot_every = Some(parse_limit(&args[i], &args[i + 1]).max(1));
+                i += 1;
+            }
+            "--snapshot-at-read" => snapshot_at_read = true,
+            "--restore" if i + 1 < args.len() => {
+                restore_file = Some(&args[i + 1]);
+                i += 1;
+            }
+            "--max-steps" if i + 1 < args.len() => {
+                limits.max_steps = Some(parse_limit(&args[i], &args[i + 1]) as u64);
+                i += 1;
+            }
+            "--timeout" if i + 1 < args.len() => {
+                limits.timeout = Some(Duration::from_millis(parse_limit(&args[i], &args[i + 1]) as u64));
+                i += 1;
+            }
+            "--max-output" if i + 1 < args.len() => {
+                limits.max_output = Some(parse_limit(&args[i], &args[i + 1]));
+                i += 1;
+            }
+            "--max-string" if i + 1 < args.len() => {
+                limits.max_string = Some(parse_limit(&args[i], &args[i + 1]));
+                i += 1;
+            }
+            "--max-array" if i + 1 < args.len() => {
+                limits.max_array = Some(parse_limit(&args[i], &args[i + 1]));
+                i += 1;
+            }
+            t if VALUE_OPTIONS.contains(&t) => usage(&args[0], Some(&format!("ERROR option {} expects a value", t))),
+            t if t.starts_with("--") => usage(&args[0], Some(&format!("ERROR unknown option {}", t))),
+            t => match filename {
+                Some(first) => usage(&args[0], Some(&format!("ERROR unexpected argument {}, the program is already {}", t, first))),
+                None => filename = Some(t)
+            }
+        }
+        i += 1;
+    }
+    let integer_mode = match IntegerMode::from_name(integers) {
+        Some(t) => t,
+        None => {
+            eprintln!("ERROR unknown integer mode {}, expected checked, wrapping, saturating or big", integers);
+            std::process::exit(1);
+        }
+    };
+    if repl {
+        if let Err(e) = Repl::new(integer_mode, limits).run() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let filename = match filename {
+        Some(t) => t,
+        None => usage(&args[0], None)
+    };
+    let backend = match Backend::from_name(backend) {
+        Some(t) => t,
+        None => {
+            eprintln!("ERROR unknown engine {}, expected tree or vm", backend);
+            std::process::exit(1);
+        }
+    };
+    if (snapshot_every.is_some() || snapshot_at_read) != snapshot_file.is_some() {
+        eprintln!("ERROR --snapshot needs --snapshot-every or --snapshot-at-read, and they need --snapshot");
+        std::process::exit(1);
+    }
+    let profile_format = match ProfileFormat::from_name(profile_format) {
+        Some(t) => t,
+        None => {
+            eprintln!("ERROR unknown profile format {}, expected text or json", profile_format);
+            std::process::exit(1);
+        }
+    };
+    let trace_format = match TraceFormat::from_name(trace_format) {
+        Some(t) => t,
+        None => {
+            eprintln!("ERROR unknown trace format {}, expected text or json", trace_format);
+            std::process::exit(1);
+        }
+    };
+    let mut engine = Engine::new()
+        .with_backend(backend)
+        .with_integer_mode(integer_mode)
+        .with_limits(limits)
+        .with_strict(strict);
+    if trace {
+        engine = engine.with_hook(Box::new(Tracer::new(trace_format, report_output(trace_file, "trace"))));
+    }
+    if profile {
+        engine = engine.with_hook(Box::new(Profiler::new(profile_format, report_output(profile_file, "profile"))));
+    }
+    let coverage = coverage_file.map(|path| Coverage::new(path, coverage_merge));
+    if let Some(t) = &coverage {
+        engine = engine.with_hook(Box::new(t.clone()));
+    }
+    if debug {
+        engine = engine.with_hook(Box::new(Debugger::new(filename)));
+    }
+    if let Some(path) = restore_file {
+        match Snapshot::load(Path::new(path)) {
+            Ok(t) => engine = engine.with_restore(t),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(path) = snapshot_file.cloned() {
+        let restored = restore_file.is_some();
+        let mut executed = 0;
+        engine = engine.with_checkpoint(move |interpreter: &Interpreter| {
+            // A run restored at a read executes it instead of stopping again
+            let paused = snapshot_at_read && (!restored || executed > 0) && matches!(interpreter.next_statement(), Some(AstItem::Read(_)));
+            let periodic = snapshot_every.is_some_and(|n| executed > 0 && executed % n == 0);
+            executed += 1;
+            if paused || periodic {
+                if let Err(e) = interpreter.snapshot().save(Path::new(&path)) {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+            if paused {
+                eprintln!("paused before read, snapshot saved to {}", path);
+            }
+            !paused
+        });
+    }
+    let result = engine.run_file(Path::new(filename));
+    // Coverage is written for failed runs too, a write error only fails an otherwise good run
+    let saved = match &coverage {
+        Some(t) => t.save().map_err(|e| format!("ERROR cannot write coverage file {}: {}", t.path(), e)),
+        None => Ok(())
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        if let Err(e) = saved {
+            eprintln!("{}", e);
+        }
+        std::process::exit(e.exit_code());
+    }
+    if let Err(e) = saved {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
 
-    let scan_result = scanner.scan();
-    //println!("{:#?}", scan_result);
-    let mut parser = SyntaxParser::new(scan_result.unwrap());
-    let parse_result = parser.parse();
-    //println!("{:#?}", parser.parse());
-    let mut interpreter = Interpreter::new(parse_result);
-    interpreter.interpret();
+// Reports go to stderr unless a file is given
+fn report_output(path: Option<&String>, report: &str) -> Box<dyn Write> {
+    match path {
+        Some(path) => match File::create(path) {
+            Ok(t) => Box::new(BufWriter::new(t)),
+            Err(e) => {
+                eprintln!("ERROR cannot create {} file {}: {}", report, path, e);
+                std::process::exit(1);
+            }
+        },
+        None => Box::new(std::io::stderr())
+    }
+}
+
+fn usage(program: &str, error: Option<&str>) -> ! {
+    if let Some(t) = error {
+        eprintln!("{}", t);
+    }
+    eprintln!("usage: {} [debug] [--engine tree|vm] [--integers checked|wrapping|saturating|big] [--strict] [--trace] [--trace-format text|json] [--trace-file <path>] [--profile] [--profile-format text|json] [--profile-file <path>] [--coverage <path>] [--coverage-merge <path>] [--snapshot <path> [--snapshot-every N] [--snapshot-at-read]] [--restore <path>] [--max-steps N] [--timeout MS] [--max-output BYTES] [--max-string CHARS] [--max-array ELEMENTS] <file>", program);
+    eprintln!("       {} repl [--integers checked|wrapping|saturating|big] [--max-steps N] [--timeout MS] [--max-output BYTES] [--max-string CHARS] [--max-array ELEMENTS]", program);
+    std::process::exit(1);
+}
 
+fn parse_limit(flag: &str, value: &str) -> usize {
+    match value.parse() {
+        Ok(t) => t,
+        Err(_) => {
+            eprintln!("ERROR {} expects a non-negative number, found {}", flag, value);
+            std::process::exit(1);
+        }
+    }
 }
 
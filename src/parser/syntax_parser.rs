@@ -1,12 +1,16 @@
-
-
 use std::mem::{self, Discriminant};
-use std::{panic};
 use std::collections::HashMap;
 use crate::language::lex::LexItemInfo;
-use crate::{language::{lex::{LexItem}, ast::{VariableInfo, VariableType, ConstantInfo, BinOpType}}, data_structures::tree::ArenaTree};
+use crate::{language::{lex::{LexItem}, ast::{VariableInfo, VariableType, ConstantInfo, BinOpType, CallInfo}}, data_structures::tree::ArenaTree};
 use crate::language::ast::{AstItem, SourceInfo};
 
+// Arrays are allocated in full when declared, this keeps one from exhausting memory
+pub const MAX_ARRAY_SIZE: usize = 1_000_000;
+
+// Syntax errors are returned as "ERROR ..." messages with the location of the offending token.
+// The parse functions return the parsed node, or None when the input does not start with their
+// construct, and the index of its last token.
+#[derive(Clone, Default)]
 pub struct SyntaxParser {
     tokens: Vec<LexItem>,
     variables: HashMap<String, VariableInfo>,
@@ -16,9 +20,6 @@ pub struct SyntaxParser {
 impl SyntaxParser {
 
     pub fn new(tokens: Vec<LexItem>) -> Self{
-        if tokens.len() <= 0 {
-            panic!("ERROR Token stream empty!");
-        }
         Self {
             tokens,
             variables: HashMap::new(),
@@ -26,249 +27,379 @@ impl SyntaxParser {
         }
     }
 
-    pub fn parse(&mut self) -> ArenaTree<AstItem> {
+    pub fn parse(&mut self) -> Result<ArenaTree<AstItem>, String> {
+        self.parse_stmts()
+    }
+
+    // Parses further statements against the variables declared by earlier input, for the REPL
+    pub fn parse_more(&mut self, tokens: Vec<LexItem>) -> Result<ArenaTree<AstItem>, String> {
+        self.tokens = tokens;
+        self.ast = ArenaTree::default();
         self.parse_stmts()
     }
 
-    fn parse_stmts(&mut self) -> ArenaTree<AstItem>{
+    // Parses a lone expression followed by a semicolon, the expression becomes the only child
+    // of the root
+    pub fn parse_expression(&mut self, tokens: Vec<LexItem>) -> Result<ArenaTree<AstItem>, String> {
+        if tokens.is_empty() {
+            return Err("ERROR Token stream empty!".to_string());
+        }
+        self.tokens = tokens;
+        self.ast = ArenaTree::default();
+        let root = self.ast.node(AstItem::Root);
+        let expr = match self.parse_expr(0)? {
+            (Some(t), end) => (t, end),
+            (None, _) => return Err(format!("ERROR expected an expression, found {}", describe(&self.tokens, 0)))
+        };
+        match self.tokens.get(expr.1 + 1) {
+            Some(LexItem::StatementEnd(_)) if expr.1 + 2 == self.tokens.len() => {
+                self.ast.arena[root].children.push(expr.0);
+                self.ast.arena[expr.0].parent = Some(root);
+            }
+            _ => return Err(expected(&self.tokens, "';' after the expression", expr.1 + 1))
+        }
+        Ok(self.ast.clone())
+    }
+
+    fn parse_stmts(&mut self) -> Result<ArenaTree<AstItem>, String> {
+        if self.tokens.is_empty() {
+            return Err("ERROR Token stream empty!".to_string());
+        }
         let mut index = 0;
         let root = self.ast.node(AstItem::Root);
         while index < self.tokens.len() {
-            let statement = self.parse_stmt(index);
-            if statement.0 != None {
-                self.ast.arena[root].children.push(statement.0.unwrap());
-                self.ast.arena[statement.0.unwrap()].parent = Some(root);
+            let statement = self.parse_stmt(index)?;
+            if let Some(t) = statement.0 {
+                self.ast.arena[root].children.push(t);
+                self.ast.arena[t].parent = Some(root);
                 index = statement.1 + 1;
                 continue;
             }
 
-            panic!("Error in parsing statement that starts at {:#?} ", &self.tokens[index]);
+            return Err(format!("ERROR expected a statement, found {}", describe(&self.tokens, index)));
         }
-        self.ast.clone()
+        Ok(self.ast.clone())
     }
 
-    fn parse_block(&mut self, index: usize, end: Discriminant<LexItem>) -> (Option<usize>, usize) {
+    fn parse_block(&mut self, index: usize, end: Discriminant<LexItem>) -> Result<(Option<usize>, usize), String> {
         let mut i = index;
         let block_node = self.ast.node(AstItem::Block);
         while i < self.tokens.len() {
             if let LexItem::End(_) = &self.tokens[i] {
-                i += 1;       
-                if mem::discriminant(&self.tokens[i]) == end {
+                i += 1;
+                if mem::discriminant(token(&self.tokens, i)?) == end {
                     i += 1;
-                    if let LexItem::StatementEnd(_) = &self.tokens[i] {
-                        return (Some(block_node), i);
+                    if let LexItem::StatementEnd(_) = token(&self.tokens, i)? {
+                        return Ok((Some(block_node), i));
                     } else {
-                         panic!("Expected semicolon, found {:#?}", self.tokens[i]);
+                        return Err(expected(&self.tokens, "';'", i));
                     }
-                }    
+                }
             }
 
-            let statement = self.parse_stmt(i);
-            if statement.0 != None {
-                self.ast.arena[block_node].children.push(statement.0.unwrap());
-                self.ast.arena[statement.0.unwrap()].parent = Some(block_node);
+            let statement = self.parse_stmt(i)?;
+            if let Some(t) = statement.0 {
+                self.ast.arena[block_node].children.push(t);
+                self.ast.arena[t].parent = Some(block_node);
                 i = statement.1 + 1;
                 continue;
             }
-            panic!("Error in parsing statement that starts at {:#?} ", &self.tokens[i]);
+            return Err(format!("ERROR expected a statement, found {}", describe(&self.tokens, i)));
         }
-        panic!("ERROR end for {:#?} not found, EOF", end);
+        Err(format!("ERROR expected end for, found {}", describe(&self.tokens, i)))
     }
 
-    fn parse_stmt(&mut self, index: usize) -> (Option<usize>, usize) {
-        let assigment = self.parse_assigment(index);
-        if assigment.0 != None {
-            return assigment;
+    fn parse_stmt(&mut self, index: usize) -> Result<(Option<usize>, usize), String> {
+        let assigment = self.parse_assigment(index)?;
+        if assigment.0.is_some() {
+            return Ok(assigment);
         }
-        let read = self.parse_read(index);
-        if read.0 != None {
-            return read;
+        let read = self.parse_read(index)?;
+        if read.0.is_some() {
+            return Ok(read);
         }
-        let print = self.parse_print(index);
-        if print.0 != None {
-            return print;
+        let print = self.parse_print(index)?;
+        if print.0.is_some() {
+            return Ok(print);
         }
-        let assert = self.parse_assert(index);
-        if assert.0 != None {
-            return assert;
+        let assert = self.parse_assert(index)?;
+        if assert.0.is_some() {
+            return Ok(assert);
         }
-        let parse_for = self.parse_for(index);
-        if parse_for.0 != None {
-            return parse_for;
+        let parse_for = self.parse_for(index)?;
+        if parse_for.0.is_some() {
+            return Ok(parse_for);
         }
-        (None, index)
+        Ok((None, index))
     }
 
-    fn parse_expr(&mut self, index: usize) -> (Option<usize>, usize) { // <opnd> <op> <opnd> | [<Not>] <opnd>
+    fn parse_expr(&mut self, index: usize) -> Result<(Option<usize>, usize), String> { // <opnd> <op> <opnd> | [<unary_op>] <opnd>
         let mut i = index;
-        let mut has_unary_opnd = false;
-        if let LexItem::Not(_) = &self.tokens[i] {
-            has_unary_opnd = true;
+        let mut unary_item = None;
+        if let LexItem::Not(_) = token(&self.tokens, i)? {
+            unary_item = Some(AstItem::Not(self.token_source_info(i)));
+            i += 1;
+        } else if let LexItem::Minus(_) = token(&self.tokens, i)? {
+            unary_item = Some(AstItem::Negate(self.token_source_info(i)));
             i += 1;
         }
-        let opnd = self.parse_opnd(i);
-        if opnd.0 == None {
-            return (None, index)
-        } else {
-            i = opnd.1;
-        }
-        if has_unary_opnd {
-            let not_item = AstItem::Not;
-            let not_node = self.ast.node(not_item);
-            self.ast.arena[not_node].children.push(opnd.0.unwrap());
-            return (Some(not_node), i)
+        let opnd = self.parse_opnd(i)?;
+        let first = match opnd.0 {
+            Some(t) => t,
+            None => return Ok((None, index))
+        };
+        i = opnd.1;
+        if let Some(item) = unary_item {
+            let unary_node = self.ast.node(item);
+            self.ast.arena[unary_node].children.push(first);
+            self.ast.arena[first].parent = Some(unary_node);
+            return Ok((Some(unary_node), i))
         }
         i += 1;
         if i >= self.tokens.len() {
-            return (opnd.0, i-1)
+            return Ok((Some(first), i-1))
         }
         // Does it have <op>
-        let op_type;
-        match &self.tokens[i] {
-            LexItem::Plus(_) => {
-                op_type = BinOpType::Plus;
-            }
-            LexItem::Minus(_) => {
-                op_type = BinOpType::Minus;
-            }
-            LexItem::Star(_) => {
-                op_type = BinOpType::Multiply;
-            }
-            LexItem::Slash(_) => {
-                op_type = BinOpType::Divide;
-            }
-            LexItem::LessThan(_) => {
-                op_type = BinOpType::LessThan;
-            }
-            LexItem::Equal(_) => {
-                op_type = BinOpType::Equal;
-            }
-            LexItem::And(_) => {
-                op_type = BinOpType::And;
-            }
-            _ => {
-                return (opnd.0, i-1)
-            }
-        }
+        let op_type = match &self.tokens[i] {
+            LexItem::Plus(_) => BinOpType::Plus,
+            LexItem::Minus(_) => BinOpType::Minus,
+            LexItem::Star(_) => BinOpType::Multiply,
+            LexItem::Slash(_) => BinOpType::Divide,
+            LexItem::LessThan(_) => BinOpType::LessThan,
+            LexItem::GreaterThan(_) => BinOpType::GreaterThan,
+            LexItem::LessEqual(_) => BinOpType::LessEqual,
+            LexItem::GreaterEqual(_) => BinOpType::GreaterEqual,
+            LexItem::Equal(_) => BinOpType::Equal,
+            LexItem::NotEqual(_) => BinOpType::NotEqual,
+            LexItem::And(_) => BinOpType::And,
+            LexItem::Or(_) => BinOpType::Or,
+            _ => return Ok((Some(first), i-1))
+        };
+        let op_source_info = self.token_source_info(i);
         i += 1;
-        let second_opnd = self.parse_opnd(i); // Return op_node -> (opnd, second_opnd)
-        if second_opnd.0 != None {
-            let op_item = AstItem::BinOp(op_type);
+        let second_opnd = self.parse_opnd(i)?; // Return op_node -> (opnd, second_opnd)
+        if let Some(second) = second_opnd.0 {
+            let op_item = AstItem::BinOp(op_type, op_source_info);
             let op_node = self.ast.node(op_item);
-            self.ast.arena[op_node].children.push(opnd.0.unwrap());
-            self.ast.arena[op_node].children.push(second_opnd.0.unwrap());
-            self.ast.arena[opnd.0.unwrap()].parent = Some(op_node);
-            self.ast.arena[second_opnd.0.unwrap()].parent = Some(op_node);
-            return (Some(op_node), second_opnd.1)
+            self.ast.arena[op_node].children.push(first);
+            self.ast.arena[op_node].children.push(second);
+            self.ast.arena[first].parent = Some(op_node);
+            self.ast.arena[second].parent = Some(op_node);
+            return Ok((Some(op_node), second_opnd.1))
         }
 
-        (None, index)
+        Err(expected(&self.tokens, "an operand", i))
     }
 
-    fn parse_opnd(&mut self, index: usize) -> (Option<usize>, usize) { // <int_literal> | <string_literal> | <var_identifier> | "(" <expr> ")"
+    fn parse_opnd(&mut self, index: usize) -> Result<(Option<usize>, usize), String> { // <int_literal> | <string_literal> | <var_identifier> | "(" <expr> ")"
         let mut i = index;
 
-        if let LexItem::IntegerLiteral(t) = &self.tokens[i] {
-            let constant_item = AstItem::Constant(ConstantInfo {value: t.text.clone(), const_type: VariableType::Int, source_info: SourceInfo {line: t.line_number, column: t.column_number}});
+        if let LexItem::IntegerLiteral(t) = token(&self.tokens, i)? {
+            let constant_item = AstItem::Constant(ConstantInfo {value: t.text.clone(), const_type: VariableType::Int, source_info: SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}});
             let constant_node = self.ast.node(constant_item);
-            return (Some(constant_node), i)
+            return Ok((Some(constant_node), i))
         } else if let LexItem::StringLiteral(t) = &self.tokens[i] {
-            let constant_item = AstItem::Constant(ConstantInfo {value: t.text.clone(), const_type: VariableType::String, source_info: SourceInfo {line: t.line_number, column: t.column_number}});
+            let constant_item = AstItem::Constant(ConstantInfo {value: t.text.clone(), const_type: VariableType::String, source_info: SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}});
             let constant_node = self.ast.node(constant_item);
-            return (Some(constant_node), i)
+            return Ok((Some(constant_node), i))
         }else if let LexItem::BoolTrue(t) = &self.tokens[i] {
-            let constant_item = AstItem::Constant(ConstantInfo {value: t.text.clone(), const_type: VariableType::Bool, source_info: SourceInfo {line: t.line_number, column: t.column_number}});
+            let constant_item = AstItem::Constant(ConstantInfo {value: t.text.clone(), const_type: VariableType::Bool, source_info: SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}});
             let constant_node = self.ast.node(constant_item);
-            return (Some(constant_node), i)
+            return Ok((Some(constant_node), i))
         }else if let LexItem::BoolFalse(t) = &self.tokens[i] {
-            let constant_item = AstItem::Constant(ConstantInfo {value: t.text.clone(), const_type: VariableType::Bool, source_info: SourceInfo {line: t.line_number, column: t.column_number}});
+            let constant_item = AstItem::Constant(ConstantInfo {value: t.text.clone(), const_type: VariableType::Bool, source_info: SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}});
             let constant_node = self.ast.node(constant_item);
-            return (Some(constant_node), i)
+            return Ok((Some(constant_node), i))
         } else if let LexItem::Identifier(t) = &self.tokens[i] {
-            let variable_info;
-            if self.variables.contains_key(&t.text.clone()) {
-                variable_info = self.variables.get(&t.text.clone())
-            } else {
-                panic!("ERROR use of uninitialized variable, line {line}, column {column}", line = t.line_number, column = t.column_number);
+            if let Some(LexItem::LeftParen(_)) = self.tokens.get(i + 1) {
+                return self.parse_call(i);
             }
-            let variable_item = AstItem::Variable(variable_info.unwrap().clone());
+            let variable_info = match self.variables.get(&t.text) {
+                Some(v) => v,
+                None => return Err(format!("ERROR use of uninitialized variable, {}", SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}))
+            };
+            let variable_item = AstItem::Variable(VariableInfo {source_info: SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}, ..variable_info.clone()});
             let variable_node = self.ast.node(variable_item);
-            return (Some(variable_node), i)
+            return self.parse_index(variable_node, i)
         }
 
         if let LexItem::LeftParen(_) = &self.tokens[i] {
             i += 1;
-            let expr = self.parse_expr(i);
-            if expr.0 == None {
-                panic!("Expected expression after {:#?}", &self.tokens[i]);
+            let expr = self.parse_expr(i)?;
+            if expr.0.is_none() {
+                return Err(expected(&self.tokens, "an expression", i));
             }
             i = expr.1 + 1;
-            if let LexItem::RightParen(_) = &self.tokens[i] {
-                return (expr.0, i)
+            if let LexItem::RightParen(_) = token(&self.tokens, i)? {
+                return Ok((expr.0, i))
             } else {
-                panic!("Expected ')', found {:#?}", &self.tokens[i]);
+                return Err(expected(&self.tokens, "')'", i));
             }
         }
 
-        (None, index)
+        Ok((None, index))
     }
 
-    pub fn parse_for(&mut self, index: usize) -> (Option<usize>, usize) {
-        let mut i = index;
-        let variable_info;
-
-        if let LexItem::For(_) = &self.tokens[i] {
-             i += 1;
+    fn parse_call(&mut self, index: usize) -> Result<(Option<usize>, usize), String> { // <identifier> "(" [<expr> {"," <expr>}] ")"
+        let call_info;
+        if let LexItem::Identifier(t) = &self.tokens[index] {
+            call_info = CallInfo {name: t.text.clone(), source_info: SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}};
         } else {
-            return (None, index)
+            return Ok((None, index))
+        }
+        let call_node = self.ast.node(AstItem::Call(call_info));
+        let mut i = index + 2;
+        if let LexItem::RightParen(_) = token(&self.tokens, i)? {
+            return Ok((Some(call_node), i))
+        }
+        loop {
+            let arg = match self.parse_expr(i)? {
+                (Some(t), end) => (t, end),
+                (None, _) => return Err(expected(&self.tokens, "an expression", i))
+            };
+            self.ast.arena[call_node].children.push(arg.0);
+            self.ast.arena[arg.0].parent = Some(call_node);
+            i = arg.1 + 1;
+            match token(&self.tokens, i)? {
+                LexItem::Comma(_) => i += 1,
+                LexItem::RightParen(_) => return Ok((Some(call_node), i)),
+                _ => return Err(expected(&self.tokens, "',' or ')'", i))
+            }
         }
-        if let LexItem::Identifier(t) = &self.tokens[i] {
+    }
+
+    fn parse_index(&mut self, variable_node: usize, index: usize) -> Result<(Option<usize>, usize), String> { // <var_identifier> ["[" <expr> "]"]
+        let mut i = index + 1;
+        if i >= self.tokens.len() {
+            return Ok((Some(variable_node), index))
+        }
+        if let LexItem::LeftBracket(t) = &self.tokens[i] {
+            let source_info = SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()};
             i += 1;
-            if self.variables.contains_key(&t.text) {
-                variable_info = self.variables.get(&t.text).unwrap().clone();
+            let expr = match self.parse_expr(i)? {
+                (Some(t), end) => (t, end),
+                (None, _) => return Err(expected(&self.tokens, "an expression", i))
+            };
+            i = expr.1 + 1;
+            if let LexItem::RightBracket(_) = token(&self.tokens, i)? {
+                let index_node = self.ast.node(AstItem::Index(source_info));
+                self.ast.arena[index_node].children.push(variable_node);
+                self.ast.arena[index_node].children.push(expr.0);
+                self.ast.arena[variable_node].parent = Some(index_node);
+                self.ast.arena[expr.0].parent = Some(index_node);
+                return Ok((Some(index_node), i))
             } else {
-                panic!("ERROR undefined variable {} at line {}, column {}", t.text, t.line_number, t.column_number);
+                return Err(expected(&self.tokens, "']'", i));
+            }
+        }
+        Ok((Some(variable_node), index))
+    }
+
+    fn parse_array_type(&mut self, index: usize) -> Result<(VariableType, usize), String> { // "array" "[" <int_literal> "]" "of" <type>
+        let mut i = index + 1;
+        if let LexItem::LeftBracket(_) = token(&self.tokens, i)? {
+            i += 1;
+        } else {
+            return Err(expected(&self.tokens, "'['", i));
+        }
+        let size: usize;
+        if let LexItem::IntegerLiteral(t) = token(&self.tokens, i)? {
+            match t.text.parse() {
+                Ok(n) if n > MAX_ARRAY_SIZE => return Err(format!("ERROR array size {} is larger than {}, {}", n, MAX_ARRAY_SIZE, SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()})),
+                Ok(n) if n > 0 => size = n,
+                _ => return Err(format!("ERROR invalid array size {}, {}", t.text, SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}))
             }
+            i += 1;
         } else {
-            panic!("ERROR Expected identifier, found {:#?}", self.tokens[i]);
+            return Err(expected(&self.tokens, "array size", i));
         }
-        if let LexItem::In(_) = &self.tokens[i] {
+        if let LexItem::RightBracket(_) = token(&self.tokens, i)? {
             i += 1;
         } else {
-            panic!("ERROR Expected keyword in, found {:#?}", self.tokens[i]);
+            return Err(expected(&self.tokens, "']'", i));
         }
-        let expr_left = self.parse_expr(i);
-        if expr_left.0 != None {
-            i = expr_left.1 + 1;
+        if let LexItem::Of(_) = token(&self.tokens, i)? {
+            i += 1;
         } else {
-            panic!("Expected expression after {:#?}", self.tokens[i]);
+            return Err(expected(&self.tokens, "keyword of", i));
+        }
+        let element_type = match token(&self.tokens, i)? {
+            LexItem::Int(_) => VariableType::Int,
+            LexItem::String(_) => VariableType::String,
+            LexItem::Bool(_) => VariableType::Bool,
+            _ => return Err(expected(&self.tokens, "element type int, string or bool", i))
+        };
+        Ok((VariableType::Array(Box::new(element_type), size), i))
+    }
+
+    pub fn parse_for(&mut self, index: usize) -> Result<(Option<usize>, usize), String> {
+        let mut i = index;
+        let variable_info;
+
+        if let LexItem::For(_) = token(&self.tokens, i)? {
+             i += 1;
+        } else {
+            return Ok((None, index))
         }
-        if let LexItem::Range(_) = &self.tokens[i] {
+        if let LexItem::Identifier(t) = token(&self.tokens, i)? {
             i += 1;
+            match self.variables.get(&t.text) {
+                Some(v) => variable_info = VariableInfo {source_info: SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}, ..v.clone()},
+                None => return Err(format!("ERROR undefined variable {} at {}", t.text, SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}))
+            }
         } else {
-            panic!("ERROR expected range operator \"..\", found {:#?}", self.tokens[i]);
+            return Err(expected(&self.tokens, "identifier", i));
         }
-        let expr_right = self.parse_expr(i);
-        if expr_right.0 != None {
-            i = expr_right.1 + 1;
+        if let LexItem::In(_) = token(&self.tokens, i)? {
+            i += 1;
         } else {
-            panic!("Expected expression after {:#?}", self.tokens[i]);
+            return Err(expected(&self.tokens, "keyword in", i));
+        }
+        let expr_left = match self.parse_expr(i)? {
+            (Some(t), end) => (t, end),
+            (None, _) => return Err(expected(&self.tokens, "an expression", i))
+        };
+        i = expr_left.1 + 1;
+        if let LexItem::Range(_) = token(&self.tokens, i)? {
+            i += 1;
+        } else {
+            return Err(expected(&self.tokens, "range operator \"..\"", i));
+        }
+        let expr_right = match self.parse_expr(i)? {
+            (Some(t), end) => (t, end),
+            (None, _) => return Err(expected(&self.tokens, "an expression", i))
+        };
+        i = expr_right.1 + 1;
+        let mut expr_step = None;
+        if let LexItem::Step(_) = token(&self.tokens, i)? {
+            i += 1;
+            match self.parse_expr(i)? {
+                (Some(t), end) => {
+                    i = end + 1;
+                    expr_step = Some(t);
+                }
+                (None, _) => return Err(expected(&self.tokens, "an expression", i))
+            }
         }
-        if let LexItem::Do(_) = &self.tokens[i] {
+        if let LexItem::Do(_) = token(&self.tokens, i)? {
             i += 1;
         } else {
-            panic!("ERROR Expected keyword do, found {:#?}", self.tokens[i]);
+            return Err(expected(&self.tokens, "keyword do", i));
         }
-        let block_node = self.parse_block(i, mem::discriminant(&LexItem::For(LexItemInfo{text: "a".into(), line_number: -1, column_number: -1}))); // TODO to this smarter lmao (I want the type of LexItem::For to give as a parameter)
+        let block_node = self.parse_block(i, mem::discriminant(&LexItem::For(LexItemInfo{text: "a".into(), line_number: -1, column_number: -1, file: "".into()})))?;
         i = block_node.1;
-        let for_node = self.ast.node(AstItem::For);
+        let for_node = self.ast.node(AstItem::For(self.token_source_info(index)));
         let variable_node = self.ast.node(AstItem::Variable(variable_info));
         let range_node = self.ast.node(AstItem::Range);
 
-        self.ast.arena[range_node].children.push(expr_left.0.unwrap());
-        self.ast.arena[range_node].children.push(expr_right.0.unwrap());
-        self.ast.arena[expr_left.0.unwrap()].parent = Some(range_node);
-        self.ast.arena[expr_right.0.unwrap()].parent = Some(range_node);
+        self.ast.arena[range_node].children.push(expr_left.0);
+        self.ast.arena[range_node].children.push(expr_right.0);
+        self.ast.arena[expr_left.0].parent = Some(range_node);
+        self.ast.arena[expr_right.0].parent = Some(range_node);
+        if let Some(step) = expr_step {
+            self.ast.arena[range_node].children.push(step);
+            self.ast.arena[step].parent = Some(range_node);
+        }
 
         self.ast.arena[for_node].children.push(variable_node);
         self.ast.arena[for_node].children.push(range_node);
@@ -276,141 +407,168 @@ impl SyntaxParser {
         self.ast.arena[variable_node].parent = Some(for_node);
         self.ast.arena[range_node].parent = Some(for_node);
         self.ast.arena[block_node.0.unwrap()].parent = Some(for_node);
-        (Some(for_node), i)
+        Ok((Some(for_node), i))
     }
 
     //-------------------------------------------------------------------------------
-    
-    pub fn parse_read(&mut self, index: usize) -> (Option<usize>, usize) {
+
+    pub fn parse_read(&mut self, index: usize) -> Result<(Option<usize>, usize), String> {
         let mut i = index;
 
-        if let LexItem::Read(_) = &self.tokens[i] {
+        if let LexItem::Read(_) = token(&self.tokens, i)? {
             i += 1;
         } else {
-            return (None, index)
+            return Ok((None, index))
         }
         let variable_info;
-        if let LexItem::Identifier(t) = &self.tokens[i] {
-            if self.variables.contains_key(&t.text) {
-                variable_info = self.variables.get(&t.text).unwrap().clone();
-            } else {
-                panic!("Error! undefined variable {:#?}", self.tokens[i]);
+        if let LexItem::Identifier(t) = token(&self.tokens, i)? {
+            match self.variables.get(&t.text) {
+                Some(v) => variable_info = VariableInfo {source_info: SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}, ..v.clone()},
+                None => return Err(format!("ERROR undefined variable {}, {}", t.text, SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}))
             }
         } else {
-            panic!("ERROR expexted identifier after {:#?}", self.tokens[i])
-        }
-        i += 1;
-        if let LexItem::StatementEnd(_) = &self.tokens[i] {
-            let read_item = AstItem::Read;
+            return Err(expected(&self.tokens, "identifier", i));
+        }
+        let variable_item = AstItem::Variable(variable_info);
+        let variable_node = self.ast.node(variable_item);
+        let target = self.parse_index(variable_node, i)?;
+        i = target.1 + 1;
+        if let LexItem::StatementEnd(_) = token(&self.tokens, i)? {
+            let read_item = AstItem::Read(self.token_source_info(index));
             let read_note = self.ast.node(read_item);
-            let variable_item = AstItem::Variable(variable_info);
-            let variable_node = self.ast.node(variable_item);
-            self.ast.arena[read_note].children.push(variable_node);
-            self.ast.arena[variable_node].parent = Some(read_note);
-            (Some(read_note), i)
+            self.ast.arena[read_note].children.push(target.0.unwrap());
+            self.ast.arena[target.0.unwrap()].parent = Some(read_note);
+            Ok((Some(read_note), i))
         } else {
-            panic!("Expected semicolon, found {:#?}", self.tokens[i]);
+            Err(expected(&self.tokens, "';'", i))
         }
     }
 
-    fn parse_assert(&mut self, index: usize) -> (Option<usize>, usize) {
+    fn parse_assert(&mut self, index: usize) -> Result<(Option<usize>, usize), String> { // "assert" "(" <expr> ["," <expr>] ")"
         let mut i = index;
 
-        if let LexItem::Assert(_) = &self.tokens[i] {
+        if let LexItem::Assert(_) = token(&self.tokens, i)? {
             i += 1;
         } else {
-            return (None, index)
+            return Ok((None, index))
         }
 
-        if let LexItem::LeftParen(_) = &self.tokens[i] {
+        if let LexItem::LeftParen(_) = token(&self.tokens, i)? {
             i += 1;
-            let expr = self.parse_expr(i);
-            if expr.0 == None {
-                panic!("Expected expression after {:#?}", &self.tokens[i]);
-            }
+            let expr = match self.parse_expr(i)? {
+                (Some(t), end) => (t, end),
+                (None, _) => return Err(expected(&self.tokens, "an expression", i))
+            };
             i = expr.1 + 1;
-            if let LexItem::RightParen(_) = &self.tokens[i] {
-                let assert_item = AstItem::Assert;
+            // Optional message, reported when the assertion fails
+            let mut message = None;
+            if let LexItem::Comma(_) = token(&self.tokens, i)? {
+                match self.parse_expr(i + 1)? {
+                    (Some(t), end) => {
+                        message = Some(t);
+                        i = end + 1;
+                    }
+                    (None, _) => return Err(expected(&self.tokens, "a message", i + 1))
+                }
+            }
+            if let LexItem::RightParen(_) = token(&self.tokens, i)? {
+                let assert_item = AstItem::Assert(self.token_source_info(index));
                 let assert_node = self.ast.node(assert_item);
-                self.ast.arena[assert_node].children.push(expr.0.unwrap());
-                self.ast.arena[expr.0.unwrap()].parent = Some(assert_node);
+                self.ast.arena[assert_node].children.push(expr.0);
+                self.ast.arena[expr.0].parent = Some(assert_node);
+                if let Some(t) = message {
+                    self.ast.arena[assert_node].children.push(t);
+                    self.ast.arena[t].parent = Some(assert_node);
+                }
                 i += 1;
-                if let LexItem::StatementEnd(_) = &self.tokens[i] {
-                    (Some(assert_node), i)
+                if let LexItem::StatementEnd(_) = token(&self.tokens, i)? {
+                    Ok((Some(assert_node), i))
                 } else {
-                    panic!("ERROR expected ';', found {:#?}", self.tokens[i]);
+                    Err(expected(&self.tokens, "';'", i))
                 }
-                
+
             } else {
-                panic!("Expected ')', found {:#?}", &self.tokens[i]);
+                Err(expected(&self.tokens, "')'", i))
             }
         } else {
-            panic!("ERROR expected '(', found {:#?}", self.tokens[i]);
+            Err(expected(&self.tokens, "'('", i))
         }
     }
 
-    fn parse_print(&mut self, index: usize) -> (Option<usize>, usize) {
+    fn parse_print(&mut self, index: usize) -> Result<(Option<usize>, usize), String> { // "print" <expr> {"," <expr>} | "println" [<expr> {"," <expr>}]
         let mut i = index;
-        
-        if let LexItem::Print(_) = &self.tokens[i] {
-            i += 1;
-        } else {
-            return (None, index)
-        }
+        let print_item = match token(&self.tokens, i)? {
+            LexItem::Print(_) => AstItem::Print(self.token_source_info(index)),
+            LexItem::Println(_) => AstItem::Println(self.token_source_info(index)),
+            _ => return Ok((None, index))
+        };
+        i += 1;
 
-        let expr = self.parse_expr(i);
-        if expr.0 == None {
-            panic!("Expected an expression after print at {:#?}", self.tokens[i-1]);
-        } else {
-            i = expr.1;
+        let mut exprs = Vec::new();
+        let line_only = matches!(print_item, AstItem::Println(_)) && matches!(token(&self.tokens, i)?, LexItem::StatementEnd(_));
+        if !line_only {
+            loop {
+                match self.parse_expr(i)? {
+                    (Some(t), end) => {
+                        exprs.push(t);
+                        i = end + 1;
+                    }
+                    (None, _) => return Err(expected(&self.tokens, "an expression", i))
+                }
+                if let LexItem::Comma(_) = token(&self.tokens, i)? {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
         }
-        i += 1;
-        
-        if let LexItem::StatementEnd(_) = &self.tokens[i]{
-            let print_item = AstItem::Print;
+
+        if let LexItem::StatementEnd(_) = token(&self.tokens, i)? {
             let print_node = self.ast.node(print_item);
-            self.ast.arena[print_node].children.push(expr.0.unwrap());
-            (Some(print_node), i)
+            for expr in exprs {
+                self.ast.arena[print_node].children.push(expr);
+                self.ast.arena[expr].parent = Some(print_node);
+            }
+            Ok((Some(print_node), i))
         } else {
-            panic!("Expected semicolon, found {:#?}", self.tokens[i]);
+            Err(expected(&self.tokens, "';'", i))
         }
     }
 
 
-    fn parse_assigment(&mut self, index: usize) -> (Option<usize>, usize) { // "var" <var_ident> ":" <type> [":=" <expr>] | <var_ident> ":=" <expr>
+    fn parse_assigment(&mut self, index: usize) -> Result<(Option<usize>, usize), String> { // "var" <var_ident> ":" <type> [":=" <expr>] | <var_ident> ":=" <expr>
         let mut first_assign = false;
+        let mut target_index = None;
         let mut i = index;
         let var_type: VariableType;
         let var_name: String;
-        let var_line;
-        let var_column;
-        if let LexItem::Var(_) = &self.tokens[i] {
+        let var_source_info;
+        if let LexItem::Var(_) = token(&self.tokens, i)? {
             first_assign = true;
             i += 1;
         }
-        if let LexItem::Identifier(t) = &self.tokens[i] {
+        if let LexItem::Identifier(t) = token(&self.tokens, i)? {
             if first_assign {
                 if self.variables.contains_key(&t.text) {
-                    panic!("ERROR variable name already defined, line {line}, column {column}", line = t.line_number, column = t.column_number)
-                }  
+                    return Err(format!("ERROR variable name already defined, {}", SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}))
+                }
             } else if !self.variables.contains_key(&t.text) {
-                panic!("ERROR undefined variable {var_name},  line {line}, column {column}", var_name = &t.text ,line = t.line_number, column = t.column_number)
+                return Err(format!("ERROR undefined variable {}, {}", &t.text, SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}))
             }
             var_name = t.text.clone();
-            var_line = t.line_number;
-            var_column = t.column_number;
+            var_source_info = SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()};
             i += 1;
         } else {
-            return (None, index)
+            return Ok((None, index))
         }
 
         if first_assign {
-            if let LexItem::Separator(_) = &self.tokens[i] {
+            if let LexItem::Separator(_) = token(&self.tokens, i)? {
                 i += 1;
             } else {
-                return (None, index)
+                return Ok((None, index))
             }
-            if let LexItem::String(_) = &self.tokens[i] {
+            if let LexItem::String(_) = token(&self.tokens, i)? {
                 var_type = VariableType::String;
                 i += 1;
             } else if let LexItem::Bool(_) = &self.tokens[i] {
@@ -419,57 +577,80 @@ impl SyntaxParser {
             } else if let LexItem::Int(_) = &self.tokens[i] {
                 var_type = VariableType::Int;
                 i += 1;
+            } else if let LexItem::Array(_) = &self.tokens[i] {
+                let array_type = self.parse_array_type(i)?;
+                var_type = array_type.0;
+                i = array_type.1 + 1;
             } else {
-                return (None, index)
+                return Ok((None, index))
             }
-            self.variables.insert(var_name.clone(), VariableInfo {name: var_name.clone(), var_type: var_type.clone(), source_info: SourceInfo {line: var_line, column: var_column}});
-            if let LexItem::StatementEnd(_) = &self.tokens[i] {
-                return (Some(self.make_assigment_node_constant(var_name,None, var_type, var_line, var_column)), i);
+            self.variables.insert(var_name.clone(), VariableInfo {name: var_name.clone(), var_type: var_type.clone(), source_info: var_source_info.clone()});
+            if let LexItem::StatementEnd(_) = token(&self.tokens, i)? {
+                return Ok((Some(self.make_assigment_node_constant(var_name,None, var_type, var_source_info)), i));
             }
         } else {
             match self.variables.get(&var_name) {
                 Some(t) => {
                     var_type = t.var_type.clone();
                 }
-                None => panic!("Unexpected ERROR variable {var_name} is defined but not found!") // This shouldn't ever happen 
+                None => panic!("Unexpected ERROR variable {var_name} is defined but not found!") // This shouldn't ever happen
+            }
+            if let LexItem::LeftBracket(_) = token(&self.tokens, i)? {
+                let variable_item = AstItem::Variable(VariableInfo {name: var_name.clone(), var_type: var_type.clone(), source_info: var_source_info.clone()});
+                let variable_node = self.ast.node(variable_item);
+                let target = self.parse_index(variable_node, i - 1)?;
+                target_index = target.0;
+                i = target.1 + 1;
             }
         }
 
 
-        if let LexItem::Assign(_) = &self.tokens[i] {
+        if let LexItem::Assign(_) = token(&self.tokens, i)? {
             i += 1;
         } else {
-            return (None, index)
+            return Ok((None, index))
         }
 
-        let expr = self.parse_expr(i);
-        if expr.0 != None {
-            i = expr.1 + 1;
-            if let LexItem::StatementEnd(_) = &self.tokens[i] {
-                let expr_index = expr.0.unwrap();
-                let assign = self.make_assigment_node_constant(var_name, None, var_type, var_line, var_column);
-                self.ast.arena[assign].children.push(expr_index);
-                self.ast.arena[expr_index].parent = Some(assign);
-                return (Some(assign), i)
-    
-            } else {
-                panic!("Expected semicolon after {:#?}", self.tokens[i])
+        let expr_index = match self.parse_expr(i)? {
+            (Some(t), end) => {
+                i = end + 1;
+                t
             }
-        }       
+            (None, _) => return Err(expected(&self.tokens, "an expression", i))
+        };
+        if let LexItem::StatementEnd(_) = token(&self.tokens, i)? {
+            let assign = match target_index {
+                Some(target) => {
+                    let assign = self.ast.node(AstItem::Assign(var_source_info.clone()));
+                    self.ast.arena[assign].children.push(target);
+                    self.ast.arena[target].parent = Some(assign);
+                    assign
+                }
+                None => self.make_assigment_node_constant(var_name, None, var_type, var_source_info)
+            };
+            self.ast.arena[assign].children.push(expr_index);
+            self.ast.arena[expr_index].parent = Some(assign);
+            Ok((Some(assign), i))
+        } else {
+            Err(expected(&self.tokens, "';'", i))
+        }
+    }
 
-        (None, index)
+    fn token_source_info(&self, index: usize) -> SourceInfo {
+        let t = self.tokens[index].info();
+        SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()}
     }
 
-    fn make_assigment_node_constant(&mut self, name: String, value: Option<String>, var_type: VariableType, var_line: i32, var_column: i32) -> usize {
-        let assign_item: AstItem = AstItem::Assign;
+    fn make_assigment_node_constant(&mut self, name: String, value: Option<String>, var_type: VariableType, var_source_info: SourceInfo) -> usize {
+        let assign_item: AstItem = AstItem::Assign(var_source_info.clone());
         let assign = self.ast.node(assign_item);
-        let variable_item = AstItem::Variable(VariableInfo{name, var_type: var_type.clone(), source_info: SourceInfo {line: var_line, column: var_column}});
+        let variable_item = AstItem::Variable(VariableInfo{name, var_type: var_type.clone(), source_info: var_source_info.clone()});
         let variable = self.ast.node(variable_item);
 
         self.ast.arena[assign].children.push(variable);
         self.ast.arena[variable].parent = Some(assign);
-        if value != None {
-            let value_item = AstItem::Constant(ConstantInfo {value: value.unwrap_or_default(), const_type: var_type,source_info: SourceInfo {line: var_line, column: var_column}});
+        if value.is_some() {
+            let value_item = AstItem::Constant(ConstantInfo {value: value.unwrap_or_default(), const_type: var_type,source_info: var_source_info.clone()});
             let value_node = self.ast.node(value_item);
             self.ast.arena[assign].children.push(value_node);
             self.ast.arena[value_node].parent = Some(assign);
@@ -480,3 +661,29 @@ impl SyntaxParser {
 
 
 }
+
+// The token at index, running past the last one is a syntax error. Takes the token list rather
+// than the parser so the result can be held while the tree is changed.
+fn token(tokens: &[LexItem], index: usize) -> Result<&LexItem, String> {
+    match tokens.get(index) {
+        Some(t) => Ok(t),
+        None => Err(format!("ERROR unexpected {}", describe(tokens, index)))
+    }
+}
+
+fn expected(tokens: &[LexItem], what: &str, index: usize) -> String {
+    format!("ERROR expected {}, found {}", what, describe(tokens, index))
+}
+
+// The text of a token and where it is, or the end of the input after the last token
+fn describe(tokens: &[LexItem], index: usize) -> String {
+    let source_info = |t: &LexItemInfo| SourceInfo {line: t.line_number, column: t.column_number, file: t.file.clone()};
+    match tokens.get(index) {
+        Some(LexItem::StringLiteral(t)) => format!("{:?}, {}", t.text, source_info(t)),
+        Some(t) => format!("'{}', {}", t.info().text, source_info(t.info())),
+        None => match tokens.last() {
+            Some(t) => format!("end of input after {}", source_info(t.info())),
+            None => "end of input".to_string()
+        }
+    }
+}
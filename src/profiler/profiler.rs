@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use crate::interpreter::hooks::{ExecutionHook, StatementContext};
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::language::ast::SourceInfo;
+use crate::tracer::tracer::{json_string, statement_name};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileFormat {
+    Text,
+    Json
+}
+
+impl ProfileFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(ProfileFormat::Text),
+            "json" => Some(ProfileFormat::Json),
+            _ => None
+        }
+    }
+}
+
+struct StatementProfile {
+    kind: &'static str,
+    source_info: SourceInfo,
+    count: u64,
+    total: Duration, // Includes the statements nested in a for body
+    own: Duration
+}
+
+struct ActiveStatement {
+    index: usize,
+    start: Instant,
+    nested: Duration
+}
+
+// Counts executions and time of every statement node and reports the costliest ones when the program stops
+pub struct Profiler {
+    format: ProfileFormat,
+    output: Box<dyn Write>,
+    statements: HashMap<usize, StatementProfile>,
+    active: Vec<ActiveStatement>,
+    program_time: Duration
+}
+
+impl Profiler {
+
+    pub fn new(format: ProfileFormat, output: Box<dyn Write>) -> Self {
+        Self { format, output, statements: HashMap::new(), active: Vec::new(), program_time: Duration::ZERO }
+    }
+
+    fn close_statement(&mut self) {
+        let active = match self.active.pop() {
+            Some(t) => t,
+            None => return
+        };
+        let elapsed = active.start.elapsed();
+        if let Some(statement) = self.statements.get_mut(&active.index) {
+            statement.count += 1;
+            statement.total += elapsed;
+            statement.own += elapsed.saturating_sub(active.nested);
+        }
+        match self.active.last_mut() {
+            Some(parent) => parent.nested += elapsed,
+            None => self.program_time += elapsed
+        }
+    }
+
+    fn sorted_statements(&self) -> Vec<&StatementProfile> {
+        let mut statements: Vec<&StatementProfile> = self.statements.values().collect();
+        statements.sort_by(|a, b| b.total.cmp(&a.total).then(a.source_info.line.cmp(&b.source_info.line)));
+        statements
+    }
+
+    fn text_report(&self) -> String {
+        let mut sources: HashMap<String, Vec<String>> = HashMap::new();
+        let mut report = format!("profile: {} statements executed in {:.3} ms\n", self.statements.values().map(|s| s.count).sum::<u64>(), millis(self.program_time));
+        report.push_str(&format!("{:>12} {:>12} {:>7} {:>10}  {:<30} source\n", "total ms", "self ms", "%", "count", "location"));
+        for statement in self.sorted_statements() {
+            let percent = if self.program_time.is_zero() { 0.0 } else { 100.0 * statement.total.as_secs_f64() / self.program_time.as_secs_f64() };
+            let location = format!("{}:{}", statement.source_info.file, statement.source_info.line);
+            let source = source_line(&mut sources, &statement.source_info);
+            report.push_str(&format!("{:>12.3} {:>12.3} {:>6.1}% {:>10}  {:<30} {}\n", millis(statement.total), millis(statement.own), percent, statement.count, location, source.trim()));
+        }
+        report
+    }
+
+    fn json_report(&self) -> String {
+        let mut sources: HashMap<String, Vec<String>> = HashMap::new();
+        let statements: Vec<String> = self.sorted_statements().iter().map(|statement| {
+            format!("{{\"kind\":\"{}\",\"file\":{},\"line\":{},\"column\":{},\"count\":{},\"total_ms\":{:.6},\"self_ms\":{:.6},\"source\":{}}}",
+                statement.kind, json_string(&statement.source_info.file), statement.source_info.line, statement.source_info.column,
+                statement.count, millis(statement.total), millis(statement.own), json_string(source_line(&mut sources, &statement.source_info).trim()))
+        }).collect();
+        format!("{{\"total_ms\":{:.6},\"statements\":[{}]}}\n", millis(self.program_time), statements.join(","))
+    }
+}
+
+impl ExecutionHook for Profiler {
+    fn before_statement(&mut self, context: StatementContext) -> Result<(), RuntimeError> {
+        let node = &context.ast.arena[context.index].val;
+        self.statements.entry(context.index).or_insert_with(|| StatementProfile {
+            kind: statement_name(node),
+            source_info: node.source_info().cloned().unwrap_or_default(),
+            count: 0,
+            total: Duration::ZERO,
+            own: Duration::ZERO
+        });
+        self.active.push(ActiveStatement { index: context.index, start: Instant::now(), nested: Duration::ZERO });
+        Ok(())
+    }
+
+    fn after_statement(&mut self, _index: usize) {
+        self.close_statement();
+    }
+
+    fn finished(&mut self) {
+        // A failing statement never finishes, so it and its enclosing loops are charged up to now
+        while !self.active.is_empty() {
+            self.close_statement();
+        }
+        let report = match self.format {
+            ProfileFormat::Text => self.text_report(),
+            ProfileFormat::Json => self.json_report()
+        };
+        let _ = self.output.write_all(report.as_bytes());
+        let _ = self.output.flush();
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn source_line(sources: &mut HashMap<String, Vec<String>>, source_info: &SourceInfo) -> String {
+    let lines = sources.entry(source_info.file.to_string()).or_insert_with(|| {
+        match std::fs::read_to_string(&*source_info.file) {
+            Ok(t) => t.lines().map(|l| l.to_string()).collect(),
+            Err(_) => Vec::new()
+        }
+    });
+    match lines.get((source_info.line - 1) as usize) {
+        Some(text) => text.clone(),
+        None => String::new()
+    }
+}
@@ -0,0 +1,298 @@
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use crate::checker::type_checker::TypeChecker;
+use crate::data_structures::tree::ArenaTree;
+use crate::interpreter::arithmetic::IntegerMode;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::limits::Limits;
+use crate::interpreter::streams::LineStdin;
+use crate::interpreter::value::Value;
+use crate::language::ast::{AstItem, VariableType};
+use crate::language::lex::{LexItem, LexItemInfo};
+use crate::loader::source_loader::SourceLoader;
+use crate::parser::syntax_parser::SyntaxParser;
+use crate::scanner::lexical_scanner::Scanner;
+
+const SOURCE_NAME: &str = "<repl>";
+const HELP: &str = "Enter statements ending in ';' or an expression to print its value.
+  :vars         list the variables and their values
+  :type EXPR    show the type of an expression
+  :reset        forget all variables
+  :help         show this help
+  :quit         leave the REPL (or Ctrl-D)";
+
+// Stdout that remembers whether the last byte written ended a line, so the prompt can
+// start on a fresh one
+struct PromptAwareStdout {
+    line_open: Rc<Cell<bool>>
+}
+
+impl Write for PromptAwareStdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(last) = buf.last() {
+            self.line_open.set(*last != b'\n');
+        }
+        io::stdout().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+// What the REPL shows for one input
+#[derive(Debug, PartialEq)]
+pub enum Reply {
+    Incomplete, // The input continues on the next line
+    Done,
+    Value(String), // An expression's value, as echoed
+    Error(String)
+}
+
+// Reads, parses and runs one statement or expression at a time. Declarations persist between
+// inputs in both the parser's symbol table and the interpreter's variables, included files in
+// the loader.
+pub struct Repl {
+    parser: SyntaxParser,
+    loader: SourceLoader,
+    interpreter: Interpreter,
+    integer_mode: IntegerMode,
+    limits: Limits,
+    line_open: Rc<Cell<bool>>
+}
+
+impl Repl {
+
+    pub fn new(integer_mode: IntegerMode, limits: Limits) -> Self {
+        let line_open = Rc::new(Cell::new(false));
+        let interpreter = Self::new_interpreter(integer_mode, limits, &line_open);
+        Self { parser: SyntaxParser::default(), loader: SourceLoader::new(), interpreter, integer_mode, limits, line_open }
+    }
+
+    fn new_interpreter(integer_mode: IntegerMode, limits: Limits, line_open: &Rc<Cell<bool>>) -> Interpreter {
+        let mut ast = ArenaTree::default();
+        ast.node(AstItem::Root);
+        let output = PromptAwareStdout { line_open: Rc::clone(line_open) };
+        let mut interpreter = Interpreter::with_io(ast, Box::new(LineStdin::default()), Box::new(output));
+        interpreter.set_integer_mode(integer_mode);
+        interpreter.set_limits(limits);
+        interpreter
+    }
+
+    pub fn run(&mut self) -> Result<(), String> {
+        let mut editor = match DefaultEditor::new() {
+            Ok(t) => t,
+            Err(e) => return Err(format!("ERROR cannot start the line editor: {}", e))
+        };
+        let history = history_path();
+        if let Some(path) = &history {
+            let _ = editor.load_history(path);
+        }
+        let mut buffer = String::new();
+        loop {
+            if self.line_open.replace(false) {
+                println!();
+            }
+            let prompt = if buffer.is_empty() { "minipl> " } else { "   ...> " };
+            let line = match editor.readline(prompt) {
+                Ok(t) => t,
+                Err(ReadlineError::Interrupted) => {
+                    buffer.clear();
+                    continue;
+                }
+                Err(ReadlineError::Eof) => break,
+                Err(e) => {
+                    eprintln!("ERROR cannot read input: {}", e);
+                    break;
+                }
+            };
+            if buffer.is_empty() && line.trim_start().starts_with(':') {
+                let _ = editor.add_history_entry(line.trim());
+                if !self.command(line.trim()) {
+                    break;
+                }
+                continue;
+            }
+            buffer.push_str(&line);
+            buffer.push('\n');
+            if self.input(&buffer) {
+                let _ = editor.add_history_entry(buffer.trim_end());
+                buffer.clear();
+            }
+        }
+        if let Some(path) = &history {
+            let _ = editor.save_history(path);
+        }
+        Ok(())
+    }
+
+    // Returns false when the REPL should stop
+    fn command(&mut self, line: &str) -> bool {
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, "")
+        };
+        match name {
+            ":vars" => {
+                let mut names: Vec<&String> = self.interpreter.variables().keys().collect();
+                names.sort();
+                for name in names {
+                    let variable = &self.interpreter.variables()[name];
+                    println!("{} : {} = {}", name, variable.var_type, echo_value(&variable.value));
+                }
+            }
+            ":type" => {
+                let checked = self.parse_expression(argument).and_then(|ast| TypeChecker::new(&ast).check_expression());
+                match checked {
+                    Ok(t) => println!("{}", t),
+                    Err(e) => eprintln!("{}", e)
+                }
+            }
+            ":reset" => {
+                self.parser = SyntaxParser::default();
+                self.loader = SourceLoader::new();
+                self.interpreter = Self::new_interpreter(self.integer_mode, self.limits, &self.line_open);
+            }
+            ":help" => println!("{}", HELP),
+            ":quit" | ":q" => return false,
+            t => eprintln!("ERROR unknown command {}, try :help", t)
+        }
+        true
+    }
+
+    // Runs buffered input once it is complete, returns false while more lines are needed
+    fn input(&mut self, text: &str) -> bool {
+        match self.eval(text) {
+            Reply::Incomplete => return false,
+            Reply::Done => (),
+            Reply::Value(t) => println!("{}", t),
+            Reply::Error(e) => {
+                if self.line_open.replace(false) {
+                    println!();
+                }
+                eprintln!("{}", e);
+            }
+        }
+        true
+    }
+
+    // Runs one input, statements or an expression, and returns what to show for it
+    pub fn eval(&mut self, text: &str) -> Reply {
+        let tokens = match Scanner::new(text.to_string(), SOURCE_NAME).scan() {
+            Ok(t) => t,
+            Err(e) => return Reply::Error(e)
+        };
+        if tokens.is_empty() {
+            return Reply::Done;
+        }
+        if !is_statement(&tokens) {
+            let result = self.parse_expression(text).and_then(|ast| {
+                TypeChecker::new(&ast).check_expression()?;
+                self.interpreter.evaluate(ast).map_err(|e| e.to_string())
+            });
+            return match result {
+                Ok(t) => Reply::Value(echo_value(&t)),
+                Err(e) => Reply::Error(e)
+            };
+        }
+        if !is_complete(&tokens) {
+            return Reply::Incomplete;
+        }
+        // Includes are relative to the current directory. Input that does not parse or type
+        // check declares nothing, so it works on copies of the parser and the loader.
+        let mut loader = self.loader.clone();
+        let tokens = match loader.load_source(text.to_string(), SOURCE_NAME) {
+            Ok(t) => t,
+            Err(e) => return Reply::Error(e)
+        };
+        // Only includes of files included before
+        if tokens.is_empty() {
+            return Reply::Done;
+        }
+        let mut parser = self.parser.clone();
+        let ast = match parser.parse_more(tokens) {
+            Ok(t) => t,
+            Err(e) => return Reply::Error(e)
+        };
+        if let Err(e) = TypeChecker::new(&ast).check() {
+            return Reply::Error(e);
+        }
+        self.parser = parser;
+        self.loader = loader;
+        // The parser keeps the declarations of input that fails at run time, so the variables
+        // the program did not get to declare are given their defaults
+        let declarations: Vec<(String, VariableType)> = ast.arena.iter()
+            .filter_map(|t| match &t.val {
+                AstItem::Variable(v) => Some((v.name.clone(), v.var_type.clone())),
+                _ => None
+            })
+            .collect();
+        match self.interpreter.interpret_more(ast) {
+            Ok(_) => Reply::Done,
+            Err(e) => {
+                for (name, var_type) in declarations {
+                    self.interpreter.declare(&name, &var_type);
+                }
+                Reply::Error(e.to_string())
+            }
+        }
+    }
+
+    fn parse_expression(&self, text: &str) -> Result<ArenaTree<AstItem>, String> {
+        // The scanner only ends a token at a delimiter
+        let mut tokens = Scanner::new(format!("{}\n", text), SOURCE_NAME).scan()?;
+        if tokens.is_empty() {
+            return Err("ERROR expected an expression".to_string());
+        }
+        // The semicolon is optional after an expression
+        if !matches!(tokens.last(), Some(LexItem::StatementEnd(_))) {
+            let last = tokens[tokens.len() - 1].info();
+            tokens.push(LexItem::StatementEnd(LexItemInfo {
+                text: ";".to_string(),
+                line_number: last.line_number,
+                column_number: last.column_number + 1,
+                file: last.file.clone()
+            }));
+        }
+        let mut parser = self.parser.clone();
+        parser.parse_expression(tokens)
+    }
+}
+
+// Statements start with a keyword or assign to a variable, anything else is an expression
+fn is_statement(tokens: &[LexItem]) -> bool {
+    match &tokens[0] {
+        LexItem::Var(_) | LexItem::For(_) | LexItem::Read(_) | LexItem::Print(_) | LexItem::Println(_) | LexItem::Assert(_) | LexItem::Include(_) => true,
+        _ => tokens.iter().any(|t| matches!(t, LexItem::Assign(_)))
+    }
+}
+
+// Every for has its end for and the input ends with a semicolon. The for of an end for is a
+// For token too, so each closed loop contributes two of them.
+fn is_complete(tokens: &[LexItem]) -> bool {
+    let fors = tokens.iter().filter(|t| matches!(t, LexItem::For(_))).count();
+    let ends = tokens.iter().filter(|t| matches!(t, LexItem::End(_))).count();
+    fors <= 2 * ends && matches!(tokens.last(), Some(LexItem::StatementEnd(_)))
+}
+
+// Strings are quoted so that empty strings and whitespace are visible
+fn echo_value(value: &Value) -> String {
+    match value {
+        Value::String(t) => format!("{:?}", t),
+        Value::Array(elements) => {
+            let elements: Vec<String> = elements.iter().map(echo_value).collect();
+            format!("[{}]", elements.join(", "))
+        }
+        t => t.to_string()
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".minipl_history"))
+}
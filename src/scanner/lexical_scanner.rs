@@ -1,11 +1,14 @@
+use std::rc::Rc;
+
 use crate::language::lex::{LexItem, LexItemInfo};
 pub struct Scanner {
     program: String,
+    file: Rc<str>,
 }
 
 impl Scanner {
-    pub fn new(program: String) -> Self {
-        Self { program }
+    pub fn new(program: String, file: &str) -> Self {
+        Self { program, file: file.into() }
     }
     pub fn scan(&self) -> Result<Vec<LexItem>, String> {
         let mut it = self.program.chars().peekable();
@@ -14,58 +17,80 @@ impl Scanner {
         let mut column_number = 0;
         while let Some(c) = Scanner::advance(&mut it, &mut column_number) {
             match c {
-                ' ' | '+' | '-' | '*' | '<' | '&' | '!' | ';' | '(' | ')' | '=' | '\n' | '\r' => {
+                ' ' | '+' | '-' | '*' | '&' | '|' | ';' | ',' | '(' | ')' | '[' | ']' | '=' | '\n' | '\r' => {
                     //Detect one character delimeters
                     match c {
                         '+' => result.push(LexItem::Plus(LexItemInfo {
                             text: c.to_string(),
                             line_number,
                             column_number,
+                            file: self.file.clone(),
                         })),
                         '-' => result.push(LexItem::Minus(LexItemInfo {
                             text: c.to_string(),
                             line_number,
                             column_number,
+                            file: self.file.clone(),
                         })),
                         '*' => result.push(LexItem::Star(LexItemInfo {
                             text: c.to_string(),
                             line_number,
                             column_number,
+                            file: self.file.clone(),
                         })),
-                        '<' => result.push(LexItem::LessThan(LexItemInfo {
+                        '&' => result.push(LexItem::And(LexItemInfo {
                             text: c.to_string(),
                             line_number,
                             column_number,
+                            file: self.file.clone(),
                         })),
-                        '&' => result.push(LexItem::And(LexItemInfo {
+                        '|' => result.push(LexItem::Or(LexItemInfo {
                             text: c.to_string(),
                             line_number,
                             column_number,
+                            file: self.file.clone(),
                         })),
-                        '!' => result.push(LexItem::Not(LexItemInfo {
+                        ';' => result.push(LexItem::StatementEnd(LexItemInfo {
                             text: c.to_string(),
                             line_number,
                             column_number,
+                            file: self.file.clone(),
                         })),
-                        ';' => result.push(LexItem::StatementEnd(LexItemInfo {
+                        ',' => result.push(LexItem::Comma(LexItemInfo {
                             text: c.to_string(),
                             line_number,
                             column_number,
+                            file: self.file.clone(),
                         })),
                         '(' => result.push(LexItem::LeftParen(LexItemInfo {
                             text: c.to_string(),
                             line_number,
                             column_number,
+                            file: self.file.clone(),
                         })),
                         ')' => result.push(LexItem::RightParen(LexItemInfo {
                             text: c.to_string(),
                             line_number,
                             column_number,
+                            file: self.file.clone(),
+                        })),
+                        '[' => result.push(LexItem::LeftBracket(LexItemInfo {
+                            text: c.to_string(),
+                            line_number,
+                            column_number,
+                            file: self.file.clone(),
+                        })),
+                        ']' => result.push(LexItem::RightBracket(LexItemInfo {
+                            text: c.to_string(),
+                            line_number,
+                            column_number,
+                            file: self.file.clone(),
                         })),
                         '=' => result.push(LexItem::Equal(LexItemInfo {
                             text: c.to_string(),
                             line_number,
                             column_number,
+                            file: self.file.clone(),
                         })),
                         '\n' => {
                             line_number += 1;
@@ -80,6 +105,75 @@ impl Scanner {
                         }
                     }
                 }
+                '<' => {
+                    // is it <, <= or <>
+                    match it.peek() {
+                        Some('=') => {
+                            result.push(LexItem::LessEqual(LexItemInfo {
+                                text: "<=".to_string(),
+                                line_number,
+                                column_number,
+                                file: self.file.clone(),
+                            }));
+                            Scanner::advance(&mut it, &mut column_number);
+                        }
+                        Some('>') => {
+                            result.push(LexItem::NotEqual(LexItemInfo {
+                                text: "<>".to_string(),
+                                line_number,
+                                column_number,
+                                file: self.file.clone(),
+                            }));
+                            Scanner::advance(&mut it, &mut column_number);
+                        }
+                        _ => result.push(LexItem::LessThan(LexItemInfo {
+                            text: c.to_string(),
+                            line_number,
+                            column_number,
+                            file: self.file.clone(),
+                        })),
+                    }
+                }
+                '>' => {
+                    // is it > or >=
+                    match it.peek() {
+                        Some('=') => {
+                            result.push(LexItem::GreaterEqual(LexItemInfo {
+                                text: ">=".to_string(),
+                                line_number,
+                                column_number,
+                                file: self.file.clone(),
+                            }));
+                            Scanner::advance(&mut it, &mut column_number);
+                        }
+                        _ => result.push(LexItem::GreaterThan(LexItemInfo {
+                            text: c.to_string(),
+                            line_number,
+                            column_number,
+                            file: self.file.clone(),
+                        })),
+                    }
+                }
+                '!' => {
+                    // is it ! or !=
+                    match it.peek() {
+                        Some('=') => {
+                            result.push(LexItem::NotEqual(LexItemInfo {
+                                text: "!=".to_string(),
+                                line_number,
+                                column_number,
+                                file: self.file.clone(),
+                            }));
+                            Scanner::advance(&mut it, &mut column_number);
+                        }
+                        _ => result.push(LexItem::Not(LexItemInfo {
+                            text: c.to_string(),
+                            line_number,
+                            column_number,
+                            file: self.file.clone(),
+                        })),
+                    }
+                }
                 ':' => {
                     // is it : or :=
                     match it.peek() {
@@ -88,6 +182,7 @@ impl Scanner {
                                 text: ":=".to_string(),
                                 line_number,
                                 column_number,
+                                file: self.file.clone(),
                             }));
                             Scanner::advance(&mut it, &mut column_number);
                         }
@@ -95,6 +190,7 @@ impl Scanner {
                             text: ":".to_string(),
                             line_number,
                             column_number,
+                            file: self.file.clone(),
                         })),
                     }
                 }
@@ -106,6 +202,7 @@ impl Scanner {
                                     text: ":".to_string(),
                                     line_number,
                                     column_number,
+                                    file: self.file.clone(),
                                 }));
                                 Scanner::advance(&mut it, &mut column_number);
                             }
@@ -135,6 +232,7 @@ impl Scanner {
                                 text: c.to_string(),
                                 line_number,
                                 column_number,
+                                file: self.file.clone(),
                             })),
                         }
                     }
@@ -154,6 +252,7 @@ impl Scanner {
                                     text: number,
                                     line_number,
                                     column_number,
+                                    file: self.file.clone(),
                                 }));
                                 break;
                             }
@@ -193,6 +292,7 @@ impl Scanner {
                         text: st.to_string(),
                         line_number,
                         column_number,
+                        file: self.file.clone(),
                     }))
                 }
                 _ => {
@@ -200,95 +300,143 @@ impl Scanner {
                     let mut st = c.to_string();
                     loop {
                         match it.peek() {
-                            Some(n)
-                                if matches!(
-                                    n,
+                            Some(
                                     ' ' | '+'
                                         | '-'
                                         | '*'
                                         | '/'
                                         | '<'
+                                        | '>'
+                                        | '='
                                         | '&'
+                                        | '|'
                                         | '!'
                                         | ';'
+                                        | ','
                                         | ':'
                                         | '.'
                                         | '('
                                         | ')'
+                                        | '['
+                                        | ']'
                                         | '\n'
                                         | '\r'
-                                ) =>
+                            ) =>
                             {
                                 match st.as_str() {
                                     "var" => result.push(LexItem::Var(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "for" => result.push(LexItem::For(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "end" => result.push(LexItem::End(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "in" => result.push(LexItem::In(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "do" => result.push(LexItem::Do(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
+                                    })),
+                                    "step" => result.push(LexItem::Step(LexItemInfo {
+                                        text: st.to_string(),
+                                        line_number,
+                                        column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "read" => result.push(LexItem::Read(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "print" => result.push(LexItem::Print(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
+                                    })),
+                                    "println" => result.push(LexItem::Println(LexItemInfo {
+                                        text: st.to_string(),
+                                        line_number,
+                                        column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "int" => result.push(LexItem::Int(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "string" => result.push(LexItem::String(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "bool" => result.push(LexItem::Bool(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
+                                    })),
+                                    "include" => result.push(LexItem::Include(LexItemInfo {
+                                        text: st.to_string(),
+                                        line_number,
+                                        column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "assert" => result.push(LexItem::Assert(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
+                                    })),
+                                    "array" => result.push(LexItem::Array(LexItemInfo {
+                                        text: st.to_string(),
+                                        line_number,
+                                        column_number,
+                                        file: self.file.clone(),
+                                    })),
+                                    "of" => result.push(LexItem::Of(LexItemInfo {
+                                        text: st.to_string(),
+                                        line_number,
+                                        column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "true" => result.push(LexItem::BoolTrue(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
                                     })),
                                     "false" => result.push(LexItem::BoolFalse(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
                                     })),
                                     _ => result.push(LexItem::Identifier(LexItemInfo {
                                         text: st.to_string(),
                                         line_number,
                                         column_number,
+                                        file: self.file.clone(),
                                     })),
                                 }
                                 break;
@@ -299,6 +447,7 @@ impl Scanner {
                                     text: st.to_string(),
                                     line_number,
                                     column_number,
+                                    file: self.file.clone(),
                                 }));
                                 break;
                             }
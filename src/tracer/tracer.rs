@@ -0,0 +1,134 @@
+use std::io::Write;
+
+use crate::interpreter::hooks::{ExecutionHook, StatementContext};
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::interpreter::value::Value;
+use crate::language::ast::{AstItem, SourceInfo};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceFormat {
+    Text,
+    Json // One JSON object per line
+}
+
+impl TraceFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(TraceFormat::Text),
+            "json" => Some(TraceFormat::Json),
+            _ => None
+        }
+    }
+}
+
+// Logs executed statements, expression results and variable writes of the tree-walking interpreter
+pub struct Tracer {
+    format: TraceFormat,
+    output: Box<dyn Write>
+}
+
+impl Tracer {
+
+    pub fn new(format: TraceFormat, output: Box<dyn Write>) -> Self {
+        Self { format, output }
+    }
+
+    fn log(&mut self, text: String) {
+        let _ = writeln!(self.output, "{}", text);
+    }
+}
+
+impl ExecutionHook for Tracer {
+    fn before_statement(&mut self, context: StatementContext) -> Result<(), RuntimeError> {
+        let node = &context.ast.arena[context.index].val;
+        let source_info = node.source_info().cloned().unwrap_or_default();
+        let text = match self.format {
+            TraceFormat::Text => format!("{}statement {} at {}", "  ".repeat(context.depth), statement_name(node), source_info),
+            TraceFormat::Json => format!("{{\"event\":\"statement\",\"kind\":\"{}\",\"depth\":{},{}}}", statement_name(node), context.depth, json_location(&source_info))
+        };
+        self.log(text);
+        Ok(())
+    }
+
+    fn expression_evaluated(&mut self, node: &AstItem, value: &Value) {
+        let source_info = node.source_info().cloned().unwrap_or_default();
+        let text = match self.format {
+            TraceFormat::Text => format!("  value {} at {}", text_value(value), source_info),
+            TraceFormat::Json => format!("{{\"event\":\"expression\",\"value\":{},{}}}", json_value(value), json_location(&source_info))
+        };
+        self.log(text);
+    }
+
+    fn variable_written(&mut self, name: &str, index: Option<usize>, value: &Value, source_info: &SourceInfo) {
+        let text = match (self.format, index) {
+            (TraceFormat::Text, Some(i)) => format!("  write {}[{}] := {} at {}", name, i, text_value(value), source_info),
+            (TraceFormat::Text, None) => format!("  write {} := {} at {}", name, text_value(value), source_info),
+            (TraceFormat::Json, _) => {
+                let index = match index {
+                    Some(i) => i.to_string(),
+                    None => "null".to_string()
+                };
+                format!("{{\"event\":\"write\",\"variable\":{},\"index\":{},\"value\":{},{}}}", json_string(name), index, json_value(value), json_location(source_info))
+            }
+        };
+        self.log(text);
+    }
+}
+
+pub fn statement_name(node: &AstItem) -> &'static str {
+    match node {
+        AstItem::Assign(_) => "assign",
+        AstItem::Read(_) => "read",
+        AstItem::Print(_) => "print",
+        AstItem::Println(_) => "println",
+        AstItem::Assert(_) => "assert",
+        AstItem::For(_) => "for",
+        _ => "unknown"
+    }
+}
+
+// Strings are quoted so that trailing spaces and newlines stay visible
+fn text_value(value: &Value) -> String {
+    match value {
+        Value::String(t) => format!("{:?}", t),
+        Value::Array(elements) => {
+            let elements: Vec<String> = elements.iter().map(text_value).collect();
+            format!("[{}]", elements.join(", "))
+        }
+        t => t.to_string()
+    }
+}
+
+fn json_value(value: &Value) -> String {
+    match value {
+        Value::Int(t) => t.to_string(),
+        Value::Big(t) => t.to_string(),
+        Value::Bool(t) => t.to_string(),
+        Value::String(t) => json_string(t),
+        Value::Array(elements) => {
+            let elements: Vec<String> = elements.iter().map(json_value).collect();
+            format!("[{}]", elements.join(","))
+        }
+    }
+}
+
+fn json_location(source_info: &SourceInfo) -> String {
+    format!("\"file\":{},\"line\":{},\"column\":{}", json_string(&source_info.file), source_info.line, source_info.column)
+}
+
+pub fn json_string(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c)
+        }
+    }
+    escaped.push('"');
+    escaped
+}
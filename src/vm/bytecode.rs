@@ -0,0 +1,61 @@
+use crate::interpreter::runtime_error::RuntimeError;
+use crate::interpreter::value::Value;
+use crate::language::ast::{BinOpType, SourceInfo, VariableType};
+
+// Variables are addressed by slot, constants and function names by their index in the chunk pools
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Constant(usize),
+    Load(usize),
+    Store(usize),
+    StoreExisting(usize), // Like Store, but the variable must already be declared
+    CheckIndexable(usize),
+    LoadIndex(usize), // Pops the index
+    StoreIndex(usize), // Pops the index and then the value
+    CheckString, // Checks the string limit for the value on top of the stack
+    Binary(BinOpType),
+    ShortCircuit(BinOpType, usize), // Replaces the left operand with the result and jumps when it decides the operator
+    SkipOperand(BinOpType, usize), // Pushes a placeholder for the right operand of an assert and jumps when the left one decides the operator
+    Not,
+    Negate,
+    Call(usize, usize), // Function and argument count
+    Print { count: usize, newline: bool }, // Pops count values and writes them in order
+    Read(VariableType),
+    Assert(usize), // Index of the assert in the chunk, pops the message and the condition operands
+    ExpectInt,
+    CheckStep,
+    Defined(usize),
+    ForInit { variable: usize, state: usize }, // Pops step, end and start of the range
+    ForCheck { state: usize, exit: usize },
+    ForNext { variable: usize, state: usize, top: usize },
+    Fail(usize) // Raises a runtime error found during compilation
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Chunk {
+    pub code: Vec<Instruction>,
+    pub spans: Vec<SourceInfo>, // Source location of each instruction, for error reporting
+    pub statements: Vec<Option<SourceInfo>>, // Location of the statement an instruction starts, if it is the first one
+    pub constants: Vec<Value>,
+    pub functions: Vec<String>,
+    pub slot_names: Vec<String>,
+    pub loop_count: usize,
+    pub errors: Vec<RuntimeError>,
+    pub asserts: Vec<AssertInfo>
+}
+
+// What an assert needs to report a failure, see operations::assert_operands
+#[derive(Debug, Clone)]
+pub struct AssertInfo {
+    pub expression: String,
+    pub operands: Vec<Option<String>>, // Text of each operand on the stack, None for constants
+    pub condition: AssertCondition,
+    pub message: bool
+}
+
+#[derive(Debug, Clone)]
+pub enum AssertCondition {
+    Value, // The condition itself is on the stack, or the variable that is its only operand
+    Binary(BinOpType, SourceInfo),
+    Not(SourceInfo)
+}
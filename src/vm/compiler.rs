@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use crate::data_structures::tree::ArenaTree;
+use crate::interpreter::arithmetic::IntegerMode;
+use crate::interpreter::operations::{assert_operand_text, assert_operands, constant_value, initial_value};
+use crate::interpreter::value::Value;
+use crate::language::ast::{AstItem, BinOpType, SourceInfo, VariableType};
+use crate::language::source_text::expression_text;
+use super::bytecode::{AssertCondition, AssertInfo, Chunk, Instruction};
+
+// Translates a type checked AST into bytecode. Instructions are emitted in the same order the
+// tree-walking interpreter evaluates the nodes, so both engines fail at the same point.
+pub struct Compiler<'a> {
+    ast: &'a ArenaTree<AstItem>,
+    chunk: Chunk,
+    slots: HashMap<String, usize>,
+    integer_mode: IntegerMode
+}
+
+impl<'a> Compiler<'a> {
+
+    pub fn new(ast: &'a ArenaTree<AstItem>) -> Self {
+        Self {
+            ast,
+            chunk: Chunk::default(),
+            slots: HashMap::new(),
+            integer_mode: IntegerMode::default()
+        }
+    }
+
+    // Int literals are converted while compiling, so the compiler needs the same mode as the machine
+    pub fn set_integer_mode(&mut self, mode: IntegerMode) {
+        self.integer_mode = mode;
+    }
+
+    pub fn compile(mut self) -> Chunk {
+        for child in &self.ast.arena[0].children {
+            self.compile_stmt(*child);
+        }
+        self.chunk
+    }
+
+    fn compile_stmt(&mut self, index: usize) {
+        let node = &self.ast.arena[index];
+        let span = self.span(index);
+        let start = self.chunk.code.len();
+        match &node.val {
+            AstItem::Print(_) | AstItem::Println(_) => {
+                for child in &node.children {
+                    self.compile_expr(*child);
+                }
+                let newline = matches!(node.val, AstItem::Println(_));
+                self.emit(Instruction::Print {count: node.children.len(), newline}, span);
+            }
+            AstItem::Assert(_) => {
+                let condition = node.children[0];
+                let operands = assert_operands(self.ast, condition);
+                let assert_condition = match &self.ast.arena[condition].val {
+                    _ if operands.is_empty() => {
+                        self.compile_expr(condition);
+                        AssertCondition::Value
+                    }
+                    AstItem::Variable(_) => AssertCondition::Value,
+                    AstItem::BinOp(t, source_info) => AssertCondition::Binary(t.clone(), source_info.clone()),
+                    AstItem::Not(source_info) => AssertCondition::Not(source_info.clone()),
+                    t => panic!("Unexpected assert condition {:#?}", t)
+                };
+                let mut skip = None;
+                for operand in &operands {
+                    self.compile_expr(*operand);
+                    if let (None, AssertCondition::Binary(op @ (BinOpType::And | BinOpType::Or), _)) = (skip, &assert_condition) {
+                        skip = Some(self.emit(Instruction::SkipOperand(op.clone(), 0), span.clone()));
+                    }
+                }
+                if let (Some(t), AssertCondition::Binary(op, _)) = (skip, &assert_condition) {
+                    self.chunk.code[t] = Instruction::SkipOperand(op.clone(), self.chunk.code.len());
+                }
+                if let Some(message) = node.children.get(1) {
+                    self.compile_expr(*message);
+                }
+                self.chunk.asserts.push(AssertInfo {
+                    expression: expression_text(self.ast, condition),
+                    operands: operands.iter().map(|t| assert_operand_text(self.ast, *t)).collect(),
+                    condition: assert_condition,
+                    message: node.children.len() > 1
+                });
+                self.emit(Instruction::Assert(self.chunk.asserts.len() - 1), span);
+            }
+            AstItem::Assign(_) => {
+                let target = &self.ast.arena[node.children[0]];
+                match &target.val {
+                    AstItem::Variable(t) => {
+                        let slot = self.slot(&t.name);
+                        if node.children.len() > 1 {
+                            self.compile_expr(node.children[1]);
+                        } else {
+                            let constant = self.constant(initial_value(&t.var_type));
+                            self.emit(Instruction::Constant(constant), span.clone());
+                        }
+                        self.emit(Instruction::Store(slot), span);
+                    }
+                    AstItem::Index(_) => {
+                        // The value is checked before the index is evaluated, as in the tree walker
+                        self.compile_expr(node.children[1]);
+                        self.emit(Instruction::CheckString, span);
+                        self.compile_element_store(node.children[0]);
+                    }
+                    _ => panic!("ERROR trying to assign value to a non variable")
+                }
+            }
+            AstItem::Read(_) => {
+                let target = &self.ast.arena[node.children[0]];
+                match &target.val {
+                    AstItem::Variable(t) => {
+                        let slot = self.slot(&t.name);
+                        self.emit(Instruction::Read(t.var_type.clone()), span);
+                        self.emit(Instruction::StoreExisting(slot), t.source_info.clone());
+                    }
+                    AstItem::Index(_) => {
+                        let element_type = match &self.ast.arena[target.children[0]].val {
+                            AstItem::Variable(v) => match &v.var_type {
+                                VariableType::Array(element_type, _) => *element_type.clone(),
+                                _ => panic!("ERROR trying to index non-array variable {}", v.name)
+                            },
+                            _ => panic!("ERROR trying to assign value to a non variable")
+                        };
+                        self.emit(Instruction::Read(element_type), span.clone());
+                        self.emit(Instruction::CheckString, span);
+                        self.compile_element_store(node.children[0]);
+                    }
+                    _ => panic!("ERROR trying to assign value to a non variable")
+                }
+            }
+            AstItem::For(_) => {
+                let variable = match &self.ast.arena[node.children[0]].val {
+                    AstItem::Variable(t) => t.clone(),
+                    t => panic!("ERROR for statement expected variable, found {:#?}", t)
+                };
+                let range = &self.ast.arena[node.children[1]];
+                let block = &self.ast.arena[node.children[2]];
+                let slot = self.slot(&variable.name);
+                self.emit(Instruction::Defined(slot), variable.source_info);
+                for range_child in &range.children {
+                    self.compile_int(*range_child);
+                }
+                if range.children.len() > 2 {
+                    let step_span = self.span(range.children[2]);
+                    self.emit(Instruction::CheckStep, step_span);
+                } else {
+                    let constant = self.constant(Value::Int(1));
+                    self.emit(Instruction::Constant(constant), span.clone());
+                }
+                let state = self.chunk.loop_count;
+                self.chunk.loop_count += 1;
+                self.emit(Instruction::ForInit {variable: slot, state}, span.clone());
+                let top = self.emit(Instruction::ForCheck {state, exit: 0}, span.clone());
+                for child in &block.children {
+                    self.compile_stmt(*child);
+                }
+                self.emit(Instruction::ForNext {variable: slot, state, top}, span);
+                let exit = self.chunk.code.len();
+                self.chunk.code[top] = Instruction::ForCheck {state, exit};
+            }
+            t => panic!("Unexpected node {:#?}", t)
+        }
+        self.chunk.statements[start] = Some(self.span(index));
+    }
+
+    fn compile_expr(&mut self, index: usize) {
+        let node = &self.ast.arena[index];
+        let span = self.span(index);
+        match &node.val {
+            AstItem::Constant(t) => {
+                match constant_value(t, self.integer_mode) {
+                    Ok(value) => {
+                        let constant = self.constant(value);
+                        self.emit(Instruction::Constant(constant), span);
+                    }
+                    Err(e) => {
+                        self.chunk.errors.push(e);
+                        let error = self.chunk.errors.len() - 1;
+                        self.emit(Instruction::Fail(error), span);
+                    }
+                }
+            }
+            AstItem::Variable(t) => {
+                let slot = self.slot(&t.name);
+                self.emit(Instruction::Load(slot), span);
+            }
+            AstItem::Index(_) => {
+                let slot = self.compile_element_address(index);
+                self.emit(Instruction::LoadIndex(slot), span);
+            }
+            AstItem::Call(t) => {
+                for child in &node.children {
+                    self.compile_expr(*child);
+                }
+                let function = match self.chunk.functions.iter().position(|f| f == &t.name) {
+                    Some(f) => f,
+                    None => {
+                        self.chunk.functions.push(t.name.clone());
+                        self.chunk.functions.len() - 1
+                    }
+                };
+                self.emit(Instruction::Call(function, node.children.len()), t.source_info.clone());
+            }
+            AstItem::Not(_) => {
+                self.compile_expr(node.children[0]);
+                self.emit(Instruction::Not, span);
+            }
+            AstItem::Negate(_) => {
+                self.compile_expr(node.children[0]);
+                self.emit(Instruction::Negate, span);
+            }
+            AstItem::BinOp(op, _) => {
+                self.compile_expr(node.children[0]);
+                let jump = match op {
+                    BinOpType::And | BinOpType::Or => Some(self.emit(Instruction::ShortCircuit(op.clone(), 0), span.clone())),
+                    _ => None
+                };
+                self.compile_expr(node.children[1]);
+                self.emit(Instruction::Binary(op.clone()), span);
+                if let Some(t) = jump {
+                    self.chunk.code[t] = Instruction::ShortCircuit(op.clone(), self.chunk.code.len());
+                }
+            }
+            t => panic!("Error, unexpected node {:#?}", t)
+        }
+    }
+
+    fn compile_int(&mut self, index: usize) {
+        self.compile_expr(index);
+        let span = self.span(index);
+        self.emit(Instruction::ExpectInt, span);
+    }
+
+    // Emits the checks of an index node and leaves the index on the stack, returns the indexed slot
+    fn compile_element_address(&mut self, index: usize) -> usize {
+        let node = &self.ast.arena[index];
+        let variable = match &self.ast.arena[node.children[0]].val {
+            AstItem::Variable(t) => t.clone(),
+            _ => panic!("Error, unexpected index node {:#?}", node.children)
+        };
+        let slot = self.slot(&variable.name);
+        self.emit(Instruction::CheckIndexable(slot), variable.source_info);
+        self.compile_int(node.children[1]);
+        slot
+    }
+
+    fn compile_element_store(&mut self, index: usize) {
+        let slot = self.compile_element_address(index);
+        let span = self.span(index);
+        self.emit(Instruction::StoreIndex(slot), span);
+    }
+
+    fn emit(&mut self, instruction: Instruction, span: SourceInfo) -> usize {
+        self.chunk.code.push(instruction);
+        self.chunk.spans.push(span);
+        self.chunk.statements.push(None);
+        self.chunk.code.len() - 1
+    }
+
+    fn constant(&mut self, value: Value) -> usize {
+        match self.chunk.constants.iter().position(|c| c == &value) {
+            Some(t) => t,
+            None => {
+                self.chunk.constants.push(value);
+                self.chunk.constants.len() - 1
+            }
+        }
+    }
+
+    fn slot(&mut self, name: &str) -> usize {
+        if let Some(t) = self.slots.get(name) {
+            return *t;
+        }
+        self.chunk.slot_names.push(name.to_string());
+        let slot = self.chunk.slot_names.len() - 1;
+        self.slots.insert(name.to_string(), slot);
+        slot
+    }
+
+    fn span(&self, index: usize) -> SourceInfo {
+        self.ast.arena[index].val.source_info().cloned().unwrap_or_default()
+    }
+}
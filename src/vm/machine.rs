@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Cursor, Write};
+
+use crate::interpreter::arithmetic::{loop_value, IntegerMode};
+use crate::interpreter::builtins::call_function;
+use crate::interpreter::limits::{LimitTracker, Limits};
+use crate::interpreter::natives::NativeRegistry;
+use crate::interpreter::operations::{assert_condition, assertion_failed, binary_operation, check_assert, check_index, element_at, expect_int, negate, not, print_value, read_value, short_circuit};
+use crate::interpreter::runtime_error::{RuntimeError, RuntimeErrorKind};
+use crate::interpreter::streams::OutputBuffer;
+use crate::interpreter::value::Value;
+use super::bytecode::{AssertCondition, Chunk, Instruction};
+
+pub struct VirtualMachine {
+    chunk: Chunk,
+    slots: Vec<Option<Value>>, // None until the variable is declared
+    loops: Vec<LoopState>,
+    stack: Vec<Value>,
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    integer_mode: IntegerMode,
+    limits: LimitTracker,
+    natives: NativeRegistry
+}
+
+#[derive(Clone, Default)]
+struct LoopState {
+    counter: i64,
+    remaining: i64,
+    step: i64
+}
+
+impl VirtualMachine {
+
+    // Reads from stdin and prints to stdout
+    pub fn new(chunk: Chunk) -> Self {
+        Self::with_io(chunk, Box::new(std::io::stdin().lock()), Box::new(std::io::stdout()))
+    }
+
+    pub fn with_io(chunk: Chunk, input: Box<dyn BufRead>, output: Box<dyn Write>) -> Self {
+        Self {
+            slots: vec![None; chunk.slot_names.len()],
+            loops: vec![LoopState::default(); chunk.loop_count],
+            chunk,
+            stack: Vec::new(),
+            input,
+            output,
+            integer_mode: IntegerMode::default(),
+            limits: LimitTracker::new(Limits::default()),
+            natives: NativeRegistry::new()
+        }
+    }
+
+    // Reads from the given text and collects everything printed into the returned buffer
+    pub fn with_buffers(chunk: Chunk, input: &str) -> (Self, OutputBuffer) {
+        let output = OutputBuffer::new();
+        let machine = Self::with_io(chunk, Box::new(Cursor::new(input.as_bytes().to_vec())), Box::new(output.clone()));
+        (machine, output)
+    }
+
+    pub fn set_integer_mode(&mut self, mode: IntegerMode) {
+        self.integer_mode = mode;
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = LimitTracker::new(limits);
+    }
+
+    pub fn set_natives(&mut self, natives: NativeRegistry) {
+        self.natives = natives;
+    }
+
+    // The declared variables by name
+    pub fn variables(&self) -> HashMap<String, Value> {
+        self.chunk.slot_names.iter().zip(&self.slots)
+            .filter_map(|(name, value)| value.as_ref().map(|t| (name.clone(), t.clone())))
+            .collect()
+    }
+
+    pub fn run(&mut self) -> Result<(), RuntimeError> {
+        self.limits.start();
+        let mut pc = 0;
+        while pc < self.chunk.code.len() {
+            if let Some(statement) = &self.chunk.statements[pc] {
+                self.limits.count_statement(statement)?;
+            }
+            pc = self.execute(pc)?;
+        }
+        Ok(())
+    }
+
+    // Executes the instruction at pc and returns the index of the next one
+    fn execute(&mut self, pc: usize) -> Result<usize, RuntimeError> {
+        let span = &self.chunk.spans[pc];
+        match &self.chunk.code[pc] {
+            Instruction::Constant(t) => {
+                self.stack.push(self.chunk.constants[*t].clone());
+            }
+            Instruction::Load(t) => {
+                match &self.slots[*t] {
+                    Some(value) => self.stack.push(value.clone()),
+                    None => return Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(self.chunk.slot_names[*t].clone()), span.clone()))
+                }
+            }
+            Instruction::Store(t) => {
+                let value = pop(&mut self.stack);
+                self.limits.check_string(&value, span)?;
+                // Declared arrays are compiled to constants, the parser bounds their size
+                if let Value::Array(elements) = &value {
+                    self.limits.check_array(elements.len(), span)?;
+                }
+                self.slots[*t] = Some(value);
+            }
+            Instruction::StoreExisting(t) => {
+                let value = pop(&mut self.stack);
+                self.limits.check_string(&value, span)?;
+                match &mut self.slots[*t] {
+                    Some(slot) => *slot = value,
+                    None => return Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(self.chunk.slot_names[*t].clone()), span.clone()))
+                }
+            }
+            Instruction::CheckIndexable(t) => {
+                match &self.slots[*t] {
+                    Some(Value::Array(_) | Value::String(_)) => (),
+                    Some(value) => return Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {expected: "array or string".to_string(), found: value.to_string()}, span.clone())),
+                    None => return Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(self.chunk.slot_names[*t].clone()), span.clone()))
+                }
+            }
+            Instruction::LoadIndex(t) => {
+                let index = pop_int(&mut self.stack);
+                let container = self.slots[*t].as_ref().unwrap();
+                let index = check_index(&self.chunk.slot_names[*t], container, index, span)?;
+                self.stack.push(element_at(container, index));
+            }
+            Instruction::StoreIndex(t) => {
+                let index = pop_int(&mut self.stack);
+                let value = pop(&mut self.stack);
+                let name = &self.chunk.slot_names[*t];
+                let index = check_index(name, self.slots[*t].as_ref().unwrap(), index, span)?;
+                match &mut self.slots[*t] {
+                    Some(Value::Array(elements)) => elements[index] = value,
+                    _ => panic!("ERROR trying to index non-array variable {}", name)
+                }
+            }
+            Instruction::CheckString => {
+                self.limits.check_string(self.stack.last().expect("UNEXPECTED ERROR value stack is empty"), span)?;
+            }
+            Instruction::Binary(op) => {
+                let right = pop(&mut self.stack);
+                let left = pop(&mut self.stack);
+                self.stack.push(binary_operation(op, left, right, self.integer_mode, span)?);
+            }
+            Instruction::ShortCircuit(op, exit) => {
+                let left = self.stack.last_mut().expect("UNEXPECTED ERROR value stack is empty");
+                if let Some(result) = short_circuit(op, left) {
+                    *left = result;
+                    return Ok(*exit);
+                }
+            }
+            Instruction::SkipOperand(op, exit) => {
+                let left = self.stack.last().expect("UNEXPECTED ERROR value stack is empty");
+                if short_circuit(op, left).is_some() {
+                    self.stack.push(Value::Bool(false));
+                    return Ok(*exit);
+                }
+            }
+            Instruction::Not => {
+                let value = pop(&mut self.stack);
+                self.stack.push(not(value, span)?);
+            }
+            Instruction::Negate => {
+                let value = pop(&mut self.stack);
+                self.stack.push(negate(value, self.integer_mode, span)?);
+            }
+            Instruction::Call(function, argc) => {
+                let args = self.stack.split_off(self.stack.len() - argc);
+                self.stack.push(call_function(&self.chunk.functions[*function], &args, self.integer_mode, &self.natives, span)?);
+            }
+            Instruction::Print {count, newline} => {
+                let mut values = self.stack.split_off(self.stack.len() - count);
+                if *newline {
+                    values.push(Value::String("\n".to_string()));
+                }
+                for value in &values {
+                    self.limits.count_output(value, span)?;
+                    print_value(&mut self.output, value, span)?;
+                }
+            }
+            Instruction::Read(var_type) => {
+                let value = read_value(&mut self.input, var_type, self.integer_mode, span)?;
+                self.stack.push(value);
+            }
+            Instruction::Assert(t) => {
+                let info = &self.chunk.asserts[*t];
+                let message = if info.message { Some(pop(&mut self.stack)) } else { None };
+                let values = self.stack.split_off(self.stack.len() - info.operands.len());
+                let mut evaluated = vec![true; values.len()];
+                let value = match &info.condition {
+                    AssertCondition::Value if values.is_empty() => pop(&mut self.stack),
+                    AssertCondition::Value => values[0].clone(),
+                    AssertCondition::Binary(op, source_info) => {
+                        // The right operand is a placeholder when SkipOperand jumped over it
+                        evaluated[1] = short_circuit(op, &values[0]).is_none();
+                        assert_condition(op, &values[0], Some(&values[1]).filter(|_| evaluated[1]), self.integer_mode, source_info)?
+                    }
+                    AssertCondition::Not(source_info) => not(values[0].clone(), source_info)?
+                };
+                if !check_assert(value, span)? {
+                    let reported = info.operands.iter().zip(values.iter().zip(evaluated))
+                        .filter_map(|(text, (value, evaluated))| text.clone().map(|t| (t, Some(value).filter(|_| evaluated))))
+                        .collect();
+                    return Err(assertion_failed(info.expression.clone(), reported, message, span));
+                }
+            }
+            Instruction::ExpectInt => {
+                let value = pop(&mut self.stack);
+                self.stack.push(Value::Int(expect_int(value, span)?));
+            }
+            Instruction::CheckStep => {
+                if let Some(Value::Int(0)) = self.stack.last() {
+                    return Err(RuntimeError::new(RuntimeErrorKind::ZeroStep, span.clone()));
+                }
+            }
+            Instruction::Defined(t) => {
+                if self.slots[*t].is_none() {
+                    return Err(RuntimeError::new(RuntimeErrorKind::UndefinedVariable(self.chunk.slot_names[*t].clone()), span.clone()));
+                }
+            }
+            Instruction::ForInit {variable, state} => {
+                let step = pop_int(&mut self.stack) as i64;
+                let right = pop_int(&mut self.stack) as i64;
+                let left = pop_int(&mut self.stack) as i64;
+                // Same iteration count as the tree-walking interpreter
+                let remaining = if step > 0 && left <= right {
+                    (right - left) / step + 1
+                } else if step < 0 && left >= right {
+                    (left - right) / -step + 1
+                } else {
+                    0
+                };
+                self.loops[*state] = LoopState {counter: left, remaining, step};
+                self.slots[*variable] = Some(Value::Int(left as i32));
+            }
+            Instruction::ForCheck {state, exit} => {
+                let loop_state = &mut self.loops[*state];
+                if loop_state.remaining == 0 {
+                    return Ok(*exit);
+                }
+                loop_state.remaining -= 1;
+                self.limits.check_time(span)?;
+            }
+            Instruction::ForNext {variable, state, top} => {
+                // Each finished iteration counts as a step, so an empty body cannot loop unchecked
+                self.limits.count_statement(span)?;
+                let loop_state = &mut self.loops[*state];
+                loop_state.counter += loop_state.step;
+                self.slots[*variable] = Some(loop_value(loop_state.counter, loop_state.step, self.integer_mode, span)?);
+                return Ok(*top);
+            }
+            Instruction::Fail(t) => {
+                return Err(self.chunk.errors[*t].clone());
+            }
+        }
+        Ok(pc + 1)
+    }
+}
+
+fn pop(stack: &mut Vec<Value>) -> Value {
+    stack.pop().expect("UNEXPECTED ERROR value stack is empty")
+}
+
+fn pop_int(stack: &mut Vec<Value>) -> i32 {
+    match pop(stack) {
+        Value::Int(t) => t,
+        t => panic!("UNEXPECTED ERROR expected int on the stack, found {}", t)
+    }
+}
@@ -0,0 +1,193 @@
+use std::io::Cursor;
+
+use minipl_interpreter::interpreter::arithmetic::IntegerMode;
+use minipl_interpreter::interpreter::limits::Limits;
+use minipl_interpreter::interpreter::runtime_error::RuntimeErrorKind;
+use minipl_interpreter::interpreter::streams::OutputBuffer;
+use minipl_interpreter::{Backend, Engine, Error, Outcome};
+
+// Runs a program on both backends, checks that they agree on the output, the result and the
+// final variables, and returns what they agreed on
+fn run_both(source: &str, input: &str, mode: IntegerMode, limits: Limits) -> (String, Result<Outcome, Error>) {
+    let run = |backend| {
+        let output = OutputBuffer::new();
+        let result = Engine::new()
+            .with_io(Box::new(Cursor::new(input.as_bytes().to_vec())), Box::new(output.clone()))
+            .with_backend(backend)
+            .with_integer_mode(mode)
+            .with_limits(limits)
+            .run(source);
+        (output.contents(), result)
+    };
+    let tree = run(Backend::Tree);
+    let vm = run(Backend::Vm);
+    assert_eq!(tree, vm, "backends differ on\n{}", source);
+    tree
+}
+
+fn run(source: &str, input: &str) -> (String, Result<Outcome, Error>) {
+    run_both(source, input, IntegerMode::default(), Limits::default())
+}
+
+fn runtime_error(result: Result<Outcome, Error>) -> RuntimeErrorKind {
+    match result {
+        Err(Error::Runtime(e)) => e.kind,
+        t => panic!("expected a runtime error, found {:?}", t)
+    }
+}
+
+#[test]
+fn loops_with_steps() {
+    let source = "var i : int;\nfor i in 10..1 step -3 do\n    print i, \" \";\nend for;\nprint i;\nfor i in 1..10 step 4 do\n    print \" \", i;\nend for;\n";
+    let (output, result) = run(source, "");
+    assert_eq!(output, "10 7 4 1 -2 1 5 9");
+    assert!(result.is_ok());
+}
+
+#[test]
+fn arrays_strings_and_builtins() {
+    let source = "var a : array[3] of string;\nvar i : int;\nvar s : string := \"Mini-PL\";\nfor i in 0..2 do\n    a[i] := substr(s, i * 2, 2);\nend for;\nprintln a[0], a[1], a[2], \" \", len(s), \" \", indexOf(s, \"PL\"), \" \", upper(s[5]);\nprintln format(\"[%-4s|%04d|%5b]\", a[1], 0 - 7, (1 < 2) & (!(2 < 1)));\n";
+    let (output, _) = run(source, "");
+    assert_eq!(output, "Mini-P 7 5 P\n[ni  |-007| true]\n");
+}
+
+#[test]
+fn read_and_variables() {
+    let source = "var n : int;\nvar total : int;\nvar i : int;\nread n;\nfor i in 1..n do\n    total := total + i;\nend for;\n";
+    let (_, result) = run(source, "4");
+    let variables = result.unwrap().variables;
+    assert_eq!(variables["total"].to_string(), "10");
+    assert_eq!(variables["i"].to_string(), "5");
+}
+
+#[test]
+fn short_circuit_guards_the_right_operand() {
+    let source = "var a : array[2] of int;\nvar i : int := 5;\nprint ((i < 2) & (a[i] = 0)), ((i > 2) | (a[i] = 0));\n";
+    let (output, result) = run(source, "");
+    assert_eq!(output, "falsetrue");
+    assert!(result.is_ok());
+}
+
+#[test]
+fn runtime_errors_stop_at_the_same_point() {
+    let cases = [
+        ("print \"a\";\nprint 1 / 0;\n", "a", RuntimeErrorKind::DivisionByZero),
+        ("var a : array[2] of int;\nprint \"a\";\na[2] := 1;\n", "a", RuntimeErrorKind::IndexOutOfBounds { name: "a".to_string(), index: 2, size: 2 }),
+        ("var i : int;\nfor i in 1..3 step 0 do\nend for;\n", "", RuntimeErrorKind::ZeroStep),
+        ("print substr(\"abc\", 2, 5);\n", "", RuntimeErrorKind::SubstrOutOfBounds { start: 2, count: 5, length: 3 }),
+        ("print toInt(\"x\");\n", "", RuntimeErrorKind::InvalidConversion("x".to_string())),
+        ("var f : string := \"%d\";\nprint format(f, \"x\");\n", "", RuntimeErrorKind::InvalidFormat("field 1 expects int, found x".to_string()))
+    ];
+    for (source, expected_output, expected) in cases {
+        let (output, result) = run(source, "");
+        assert_eq!(output, expected_output);
+        assert_eq!(runtime_error(result), expected);
+    }
+}
+
+#[test]
+fn assertion_failures_report_the_same_values() {
+    let (_, result) = run("var x : int := 4;\nassert(x = (x + 1), \"off by one\");\n", "");
+    let error = result.unwrap_err();
+    assert_eq!(error.to_string(), "ERROR assertion (x = (x + 1)) failed: off by one (x = 4, (x + 1) = 5), <source>, line 2, column 6");
+    assert_eq!(error.exit_code(), 1);
+
+    let (_, result) = run("var x : int := 4;\nassert(!(x = 4));\n", "");
+    assert_eq!(result.unwrap_err().to_string(), "ERROR assertion (!(x = 4)) failed: (x = 4) = true, <source>, line 2, column 6");
+}
+
+#[test]
+fn assertion_failures_report_logical_operands_and_variables() {
+    let source = "var a : bool := 1 = 2;\nvar b : bool := 1 = 1;\nassert(a & b);\n";
+    let (_, result) = run(source, "");
+    assert_eq!(result.unwrap_err().to_string(), "ERROR assertion (a & b) failed: a = false, b = not evaluated, <source>, line 3, column 6");
+    let (_, result) = run(&source.replace("a & b", "b & a"), "");
+    assert_eq!(result.unwrap_err().to_string(), "ERROR assertion (b & a) failed: b = true, a = false, <source>, line 3, column 6");
+    let (_, result) = run(&source.replace("a & b", "a | a"), "");
+    assert_eq!(result.unwrap_err().to_string(), "ERROR assertion (a | a) failed: a = false, a = false, <source>, line 3, column 6");
+    let (_, result) = run(&source.replace("a & b", "a"), "");
+    assert_eq!(result.unwrap_err().to_string(), "ERROR assertion (a) failed: a = false, <source>, line 3, column 6");
+    let (output, result) = run("var b : bool := 1 = 1;\nassert(b | ((1 / 0) = 1));\nassert(b);\nprint \"ok\";\n", "");
+    assert_eq!(output, "ok");
+    assert!(result.is_ok());
+}
+
+#[test]
+fn read_errors() {
+    let (_, result) = run("var n : int;\nread n;\nread n;\n", "3");
+    assert!(matches!(runtime_error(result), RuntimeErrorKind::EndOfInput(_)));
+    let (_, result) = run("var n : int;\nread n;\n", "three");
+    assert!(matches!(runtime_error(result), RuntimeErrorKind::InvalidInput { .. }));
+}
+
+#[test]
+fn integer_modes() {
+    let source = "var x : int := 2147483647;\nvar i : int;\nx := x + 1;\nfor i in 2147483647..2147483647 do\nend for;\nprint x, \" \", i;\n";
+    let (_, result) = run_both(source, "", IntegerMode::Checked, Limits::default());
+    assert!(matches!(runtime_error(result), RuntimeErrorKind::IntegerOverflow(_)));
+    let (output, _) = run_both(source, "", IntegerMode::Wrapping, Limits::default());
+    assert_eq!(output, "-2147483648 -2147483648");
+    let (output, _) = run_both(source, "", IntegerMode::Saturating, Limits::default());
+    assert_eq!(output, "2147483647 2147483647");
+    let (output, _) = run_both(source, "", IntegerMode::Big, Limits::default());
+    assert_eq!(output, "2147483648 2147483648");
+}
+
+#[test]
+fn loop_variable_past_the_i32_range() {
+    // The body runs for the last value, then the variable would step past i32::MAX
+    let source = "var i : int;\nfor i in 2147483646..2147483647 do\n    print i, \" \";\nend for;\nprint i;\n";
+    let (output, result) = run_both(source, "", IntegerMode::Checked, Limits::default());
+    assert_eq!(output, "2147483646 2147483647 ");
+    assert_eq!(runtime_error(result), RuntimeErrorKind::IntegerOverflow("2147483647 + 1".to_string()));
+    let (output, _) = run_both(source, "", IntegerMode::Wrapping, Limits::default());
+    assert_eq!(output, "2147483646 2147483647 -2147483648");
+    let (output, _) = run_both(source, "", IntegerMode::Saturating, Limits::default());
+    assert_eq!(output, "2147483646 2147483647 2147483647");
+    let (output, _) = run_both(source, "", IntegerMode::Big, Limits::default());
+    assert_eq!(output, "2147483646 2147483647 2147483648");
+    // Counting down past i32::MIN
+    let source = "var i : int;\nfor i in ((0 - 2147483647) - 1)..((0 - 2147483647) - 1) step (0 - 1) do\nend for;\n";
+    let (_, result) = run_both(source, "", IntegerMode::Checked, Limits::default());
+    assert_eq!(runtime_error(result), RuntimeErrorKind::IntegerOverflow("-2147483648 + -1".to_string()));
+}
+
+#[test]
+fn limits() {
+    let limits = Limits { max_output: Some(5), ..Limits::default() };
+    let (output, result) = run_both("var i : int;\nfor i in 1..10 do\n    print i;\nend for;\n", "", IntegerMode::default(), limits);
+    assert_eq!(output, "12345");
+    assert_eq!(runtime_error(result), RuntimeErrorKind::OutputLimitExceeded(5));
+
+    // Iterations count as steps even when the body is empty
+    let limits = Limits { max_steps: Some(1000), ..Limits::default() };
+    let (_, result) = run_both("var i : int;\nfor i in 0..2000000000 do\nend for;\n", "", IntegerMode::default(), limits);
+    assert_eq!(runtime_error(result), RuntimeErrorKind::StepLimitExceeded(1000));
+
+    let limits = Limits { max_array: Some(100), ..Limits::default() };
+    let (output, result) = run_both("print \"a\";\nvar a : array[5000] of int;\n", "", IntegerMode::default(), limits);
+    assert_eq!(output, "a");
+    assert_eq!(runtime_error(result), RuntimeErrorKind::ArrayLimitExceeded { size: 5000, limit: 100 });
+
+    // An element store checks the string before it evaluates the index
+    let limits = Limits { max_string: Some(3), ..Limits::default() };
+    let source = "var a : array[2] of string;\nvar i : int := 0;\na[1 / i] := \"long\";\n";
+    let (_, result) = run_both(source, "", IntegerMode::default(), limits);
+    assert_eq!(result.unwrap_err().to_string(), "ERROR string of length 4 exceeds the limit of 3 characters, <source>, line 3, column 1");
+    let (_, result) = run_both("var a : array[2] of string;\nvar i : int := 0;\nread a[1 / i];\n", "long", IntegerMode::default(), limits);
+    assert_eq!(runtime_error(result), RuntimeErrorKind::StringLimitExceeded { length: 4, limit: 3 });
+}
+
+#[test]
+fn oversized_array_is_a_syntax_error() {
+    let (_, result) = run("var a : array[2000000000] of int;\n", "");
+    assert!(matches!(result, Err(Error::Syntax(_))), "{:?}", result);
+}
+
+#[test]
+fn missing_initializer_is_a_syntax_error() {
+    let (_, result) = run("var x : int := ;\n", "");
+    assert_eq!(result.unwrap_err().to_string(), "ERROR expected an expression, found ';', <source>, line 1, column 16");
+    let (_, result) = run("var x : int;\nx := print x;\n", "");
+    assert_eq!(result.unwrap_err().to_string(), "ERROR expected an expression, found 'print', <source>, line 2, column 10");
+}
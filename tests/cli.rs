@@ -0,0 +1,23 @@
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_minipl-interpreter")).args(args).output().expect("failed to run the interpreter")
+}
+
+// The first line of stderr, the usage text follows it
+fn usage_error(args: &[&str]) -> String {
+    let output = run(args);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("usage: "), "{}", stderr);
+    stderr.lines().next().unwrap().to_string()
+}
+
+#[test]
+fn bad_arguments_are_usage_errors() {
+    let program = concat!(env!("CARGO_MANIFEST_DIR"), "/programs/bench_loop.txt");
+    assert_eq!(usage_error(&["--egnine", "vm", program]), "ERROR unknown option --egnine");
+    assert_eq!(usage_error(&[program, "--engine"]), "ERROR option --engine expects a value");
+    assert_eq!(usage_error(&["--max-steps", "10", program, "other.txt"]), format!("ERROR unexpected argument other.txt, the program is already {}", program));
+    assert!(usage_error(&[]).starts_with("usage: "));
+}
@@ -0,0 +1,59 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use minipl_interpreter::coverage::coverage::Coverage;
+use minipl_interpreter::interpreter::streams::OutputBuffer;
+use minipl_interpreter::Engine;
+
+// The second loop never runs, so its body line is not hit and only its skip branch is taken
+const SOURCE: &str = "var i : int;\nfor i in 1..3 do\n    print i;\nend for;\nfor i in 3..1 do\n    print i;\nend for;\n";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("minipl-coverage-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(coverage: &Coverage) {
+    Engine::new()
+        .with_io(Box::new(Cursor::new(Vec::new())), Box::new(OutputBuffer::new()))
+        .with_hook(Box::new(coverage.clone()))
+        .run(SOURCE)
+        .unwrap();
+    coverage.save().unwrap();
+}
+
+fn records(path: &PathBuf, prefix: &str) -> Vec<String> {
+    fs::read_to_string(path).unwrap().lines().filter(|t| t.starts_with(prefix)).map(str::to_string).collect()
+}
+
+#[test]
+fn report_counts_lines_and_loop_branches() {
+    let path = temp_dir("report").join("run.info");
+    run(&Coverage::new(path.to_str().unwrap(), false));
+    assert_eq!(records(&path, "DA:"), ["DA:1,1", "DA:2,1", "DA:3,3", "DA:5,1", "DA:6,0"]);
+    assert_eq!(records(&path, "BRDA:"), ["BRDA:2,0,0,1", "BRDA:2,0,1,0", "BRDA:5,1,0,0", "BRDA:5,1,1,1"]);
+    assert_eq!(records(&path, "LH:"), ["LH:4"]);
+    assert_eq!(records(&path, "BRH:"), ["BRH:2"]);
+    // Without merge a second run replaces the report
+    run(&Coverage::new(path.to_str().unwrap(), false));
+    assert_eq!(records(&path, "DA:3"), ["DA:3,3"]);
+}
+
+#[test]
+fn merge_adds_the_counts_of_the_existing_report() {
+    let path = temp_dir("merge").join("merged.info");
+    let _ = fs::remove_file(&path);
+    run(&Coverage::new(path.to_str().unwrap(), true));
+    run(&Coverage::new(path.to_str().unwrap(), true));
+    assert_eq!(records(&path, "DA:"), ["DA:1,2", "DA:2,2", "DA:3,6", "DA:5,2", "DA:6,0"]);
+    assert_eq!(records(&path, "BRDA:"), ["BRDA:2,0,0,2", "BRDA:2,0,1,0", "BRDA:5,1,0,0", "BRDA:5,1,1,2"]);
+}
+
+#[test]
+fn write_errors_are_returned() {
+    let dir = temp_dir("error");
+    let coverage = Coverage::new(dir.to_str().unwrap(), false);
+    assert!(coverage.save().is_err());
+}
@@ -0,0 +1,78 @@
+use std::io::Cursor;
+
+use minipl_interpreter::debugger::debugger::Debugger;
+use minipl_interpreter::interpreter::hooks::ExecutionHook;
+use minipl_interpreter::interpreter::runtime_error::RuntimeErrorKind;
+use minipl_interpreter::interpreter::streams::OutputBuffer;
+use minipl_interpreter::profiler::profiler::{ProfileFormat, Profiler};
+use minipl_interpreter::tracer::tracer::{TraceFormat, Tracer};
+use minipl_interpreter::{Engine, Error};
+
+// Runs the program with one hook and returns the program output and the hook report
+fn run(source: &str, input: &str, hook: impl FnOnce(OutputBuffer) -> Box<dyn ExecutionHook>) -> (String, String, Result<(), Error>) {
+    let output = OutputBuffer::new();
+    let report = OutputBuffer::new();
+    let result = Engine::new()
+        .with_io(Box::new(Cursor::new(input.as_bytes().to_vec())), Box::new(output.clone()))
+        .with_hook(hook(report.clone()))
+        .run(source);
+    (output.contents(), report.contents(), result.map(|_| ()))
+}
+
+#[test]
+fn trace_lists_statements_values_and_writes() {
+    let source = "var x : int := 2;\nvar i : int;\nfor i in 1..1 do\n    x := x * 3;\nend for;\n";
+    let (_, report, result) = run(source, "", |t| Box::new(Tracer::new(TraceFormat::Text, Box::new(t))));
+    assert_eq!(result, Ok(()));
+    assert_eq!(report.lines().collect::<Vec<_>>(), [
+        "statement assign at <source>, line 1, column 5",
+        "  value 2 at <source>, line 1, column 16",
+        "  write x := 2 at <source>, line 1, column 5",
+        "statement assign at <source>, line 2, column 5",
+        "  write i := 0 at <source>, line 2, column 5",
+        "statement for at <source>, line 3, column 3",
+        "  value 1 at <source>, line 3, column 10",
+        "  value 1 at <source>, line 3, column 13",
+        "  write i := 1 at <source>, line 3, column 3",
+        "  statement assign at <source>, line 4, column 5",
+        "  value 2 at <source>, line 4, column 10",
+        "  value 3 at <source>, line 4, column 14",
+        "  value 6 at <source>, line 4, column 12",
+        "  write x := 6 at <source>, line 4, column 5",
+        "  write i := 2 at <source>, line 3, column 3"
+    ]);
+}
+
+#[test]
+fn profile_counts_each_statement_of_a_loop() {
+    let source = "var i : int;\nvar total : int := 0;\nfor i in 1..5 do\n    total := total + i;\nend for;\nprint total;\n";
+    let (output, report, result) = run(source, "", |t| Box::new(Profiler::new(ProfileFormat::Json, Box::new(t))));
+    assert_eq!(result, Ok(()));
+    assert_eq!(output, "15");
+    // Timings vary between runs, so only the line and count of each statement are compared
+    let mut counts: Vec<(String, String)> = report.split("{\"kind\"").skip(1).map(|statement| {
+        let field = |name: &str| statement.split(&format!("\"{}\":", name)).nth(1).unwrap().split(',').next().unwrap().to_string();
+        (field("line"), field("count"))
+    }).collect();
+    counts.sort();
+    let expected = [("1", "1"), ("2", "1"), ("3", "1"), ("4", "5"), ("6", "1")];
+    assert_eq!(counts, expected.map(|(line, count)| (line.to_string(), count.to_string())));
+}
+
+#[test]
+fn debugger_stops_at_breakpoints_and_changes_variables() {
+    let source = "var i : int;\nvar total : int := 0;\nfor i in 1..3 do\n    total := total + i;\nend for;\nprint total;\n";
+    let commands = "break 4\ncontinue\nprint i\nset total 100\ndelete 4\ncontinue\n";
+    let (output, _, result) = run(source, commands, |_| Box::new(Debugger::new("<source>")));
+    assert_eq!(result, Ok(()));
+    // The source lines are empty because <source> is not a file
+    assert_eq!(output, "stopped at <source>, line 1, column 5\n\n(debug) breakpoint at <source>:4\n(debug) stopped at <source>, line 4, column 9\n\n\
+        (debug) i : int = 1\n(debug) total : int = 100\n(debug) deleted breakpoint at <source>:4\n(debug) 106");
+}
+
+#[test]
+fn debugger_quit_stops_the_program() {
+    let (output, _, result) = run("print 1;\nprint 2;\n", "step\nquit\n", |_| Box::new(Debugger::new("<source>")));
+    assert!(output.ends_with("(debug) 1stopped at <source>, line 2, column 5\n\n(debug) "), "{}", output);
+    assert!(matches!(result, Err(Error::Runtime(e)) if e.kind == RuntimeErrorKind::DebuggerQuit));
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use minipl_interpreter::interpreter::streams::OutputBuffer;
+use minipl_interpreter::{Engine, Error};
+
+// Writes the files into a fresh temporary directory
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("minipl-includes-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for (file, source) in files {
+        fs::write(dir.join(file), source).unwrap();
+    }
+    dir
+}
+
+fn run(path: PathBuf) -> (String, Result<(), Error>) {
+    let output = OutputBuffer::new();
+    let result = Engine::new()
+        .with_io(Box::new(Cursor::new(Vec::new())), Box::new(output.clone()))
+        .run_file(&path);
+    (output.contents(), result.map(|_| ()))
+}
+
+fn load_error(result: Result<(), Error>) -> String {
+    match result {
+        Err(Error::Load(e)) => e,
+        t => panic!("expected a load error, found {:?}", t)
+    }
+}
+
+#[test]
+fn diamond_include_splices_the_shared_file_once() {
+    let dir = project("diamond", &[
+        ("main.mpl", "include \"left.mpl\";\ninclude \"right.mpl\";\nprint n;\n"),
+        ("left.mpl", "include \"shared.mpl\";\nn := n + 1;\n"),
+        ("right.mpl", "include \"shared.mpl\";\nn := n + 10;\n"),
+        ("shared.mpl", "var n : int := 100;\n"),
+    ]);
+    let (output, result) = run(dir.join("main.mpl"));
+    assert_eq!(result, Ok(()));
+    assert_eq!(output, "111");
+}
+
+#[test]
+fn include_cycle_is_reported_at_the_include() {
+    let dir = project("cycle", &[
+        ("a.mpl", "include \"b.mpl\";\n"),
+        ("b.mpl", "print 1;\ninclude \"a.mpl\";\n"),
+    ]);
+    let error = load_error(run(dir.join("a.mpl")).1);
+    assert!(error.starts_with("ERROR include cycle: "), "{}", error);
+    assert!(error.ends_with(&format!("a.mpl, {}, line 2, column 7", dir.join("b.mpl").display())), "{}", error);
+}
+
+#[test]
+fn missing_include_is_reported_at_the_include() {
+    let dir = project("missing", &[("main.mpl", "print 1;\n  include \"gone.mpl\";\n")]);
+    let error = load_error(run(dir.join("main.mpl")).1);
+    assert!(error.starts_with(&format!("ERROR cannot open {}: ", dir.join("gone.mpl").display())), "{}", error);
+    assert!(error.ends_with(&format!(", {}, line 2, column 9", dir.join("main.mpl").display())), "{}", error);
+}
@@ -0,0 +1,77 @@
+use std::io::Cursor;
+
+use minipl_interpreter::interpreter::natives::NativeRegistry;
+use minipl_interpreter::interpreter::runtime_error::RuntimeErrorKind;
+use minipl_interpreter::interpreter::streams::OutputBuffer;
+use minipl_interpreter::interpreter::value::Value;
+use minipl_interpreter::language::ast::VariableType;
+use minipl_interpreter::{Backend, Engine, Error};
+
+fn registry() -> NativeRegistry {
+    let mut natives = NativeRegistry::new();
+    natives.register("repeat", vec![VariableType::String, VariableType::Int], VariableType::String, |args| match args {
+        [Value::String(t), Value::Int(n)] if *n >= 0 => Ok(Value::String(t.repeat(*n as usize))),
+        [_, Value::Int(n)] => Err(format!("negative count {}", n)),
+        _ => unreachable!()
+    }).unwrap();
+    natives.register("broken", vec![], VariableType::Int, |_| Ok(Value::String("not an int".to_string()))).unwrap();
+    natives
+}
+
+fn run(source: &str, backend: Backend) -> (String, Result<(), Error>) {
+    let output = OutputBuffer::new();
+    let result = Engine::new()
+        .with_io(Box::new(Cursor::new(Vec::new())), Box::new(output.clone()))
+        .with_backend(backend)
+        .with_natives(registry())
+        .run(source);
+    (output.contents(), result.map(|_| ()))
+}
+
+fn runtime_error(result: Result<(), Error>) -> RuntimeErrorKind {
+    match result {
+        Err(Error::Runtime(e)) => e.kind,
+        t => panic!("expected a runtime error, found {:?}", t)
+    }
+}
+
+#[test]
+fn natives_are_called_on_both_backends() {
+    let source = "var s : string := repeat(\"ab\", 3);\nprint s, \" \", len(repeat(\"x\", 2 + 2));\n";
+    for backend in [Backend::Tree, Backend::Vm] {
+        let (output, result) = run(source, backend);
+        assert_eq!(result, Ok(()));
+        assert_eq!(output, "ababab 4");
+    }
+}
+
+#[test]
+fn registration_rejects_builtin_duplicate_and_invalid_names() {
+    let mut natives = registry();
+    let error = natives.register("len", vec![VariableType::String], VariableType::Int, |_| Ok(Value::Int(0))).unwrap_err();
+    assert_eq!(error, "ERROR function len is a built-in function");
+    let error = natives.register("repeat", vec![], VariableType::Int, |_| Ok(Value::Int(0))).unwrap_err();
+    assert_eq!(error, "ERROR function repeat is already registered");
+    let error = natives.register("2fast", vec![], VariableType::Int, |_| Ok(Value::Int(0))).unwrap_err();
+    assert_eq!(error, "ERROR function name 2fast is not an identifier");
+}
+
+#[test]
+fn type_checker_checks_native_calls() {
+    let (_, result) = run("print repeat(\"ab\");\n", Backend::Tree);
+    assert!(matches!(&result, Err(Error::Type(e)) if e.contains("function repeat expects 2 arguments, found 1")), "{:?}", result);
+    let (_, result) = run("print repeat(3, 3);\n", Backend::Tree);
+    assert!(matches!(&result, Err(Error::Type(e)) if e.contains("function repeat expects string argument, found int")), "{:?}", result);
+    let (_, result) = run("var i : int := repeat(\"ab\", 1);\n", Backend::Tree);
+    assert!(matches!(result, Err(Error::Type(_))), "{:?}", result);
+}
+
+#[test]
+fn failing_and_mistyped_natives_are_runtime_errors() {
+    for backend in [Backend::Tree, Backend::Vm] {
+        let (_, result) = run("print repeat(\"ab\", 0 - 1);\n", backend);
+        assert_eq!(runtime_error(result), RuntimeErrorKind::NativeFunctionFailed { name: "repeat".to_string(), message: "negative count -1".to_string() });
+        let (_, result) = run("print broken();\n", backend);
+        assert_eq!(runtime_error(result), RuntimeErrorKind::TypeMismatch { expected: "int from broken".to_string(), found: "not an int".to_string() });
+    }
+}
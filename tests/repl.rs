@@ -0,0 +1,43 @@
+use minipl_interpreter::interpreter::arithmetic::IntegerMode;
+use minipl_interpreter::interpreter::limits::Limits;
+use minipl_interpreter::repl::repl::{Repl, Reply};
+
+fn repl() -> Repl {
+    Repl::new(IntegerMode::default(), Limits::default())
+}
+
+fn error(reply: Reply) -> String {
+    match reply {
+        Reply::Error(e) => e,
+        t => panic!("expected an error, found {:?}", t)
+    }
+}
+
+#[test]
+fn declarations_persist_between_inputs() {
+    let mut repl = repl();
+    assert_eq!(repl.eval("var x : int := 2;\n"), Reply::Done);
+    assert_eq!(repl.eval("x * 3\n"), Reply::Value("6".to_string()));
+    assert_eq!(repl.eval("for x in 1..2 do\n"), Reply::Incomplete);
+    assert_eq!(repl.eval("for x in 1..2 do\nend for;\n"), Reply::Done);
+    assert_eq!(repl.eval("x\n"), Reply::Value("3".to_string()));
+}
+
+#[test]
+fn declaration_that_fails_type_checking_is_forgotten() {
+    let mut repl = repl();
+    assert!(error(repl.eval("var x : int := \"a\";\n")).starts_with("ERROR"));
+    assert!(error(repl.eval("x\n")).contains("uninitialized variable"));
+    assert_eq!(repl.eval("var x : int;\n"), Reply::Done);
+    assert_eq!(repl.eval("x\n"), Reply::Value("0".to_string()));
+}
+
+#[test]
+fn declaration_that_fails_at_run_time_keeps_its_default() {
+    let mut repl = repl();
+    assert!(error(repl.eval("var y : int := 1 / 0;\n")).contains("division by zero"));
+    assert_eq!(repl.eval("y\n"), Reply::Value("0".to_string()));
+    assert_eq!(repl.eval("y := 5;\n"), Reply::Done);
+    assert_eq!(repl.eval("y\n"), Reply::Value("5".to_string()));
+    assert!(error(repl.eval("var y : int;\n")).contains("already defined"));
+}
@@ -0,0 +1,64 @@
+use minipl_interpreter::checker::type_checker::TypeChecker;
+use minipl_interpreter::data_structures::tree::ArenaTree;
+use minipl_interpreter::interpreter::interpreter::Interpreter;
+use minipl_interpreter::interpreter::snapshot::{fingerprint, Snapshot};
+use minipl_interpreter::interpreter::value::Value;
+use minipl_interpreter::language::ast::{AstItem, VariableType};
+use minipl_interpreter::parser::syntax_parser::SyntaxParser;
+use minipl_interpreter::scanner::lexical_scanner::Scanner;
+
+const SOURCE: &str = "var a : array[3] of int;\nvar i : int;\nfor i in 0..2 do\n    a[i] := i;\nend for;\nprint a[2];\n";
+
+fn parse(source: &str, file: &str) -> ArenaTree<AstItem> {
+    let tokens = Scanner::new(source.to_string(), file).scan().unwrap();
+    let ast = SyntaxParser::new(tokens).parse().unwrap();
+    TypeChecker::new(&ast).check().unwrap();
+    ast
+}
+
+// Runs SOURCE until it has executed the given number of statements and returns its snapshot
+fn snapshot_after(statements: usize) -> Snapshot {
+    let (mut interpreter, _) = Interpreter::with_buffers(parse(SOURCE, "snap.mpl"), "");
+    let mut seen = 0;
+    let finished = interpreter.interpret_with(&mut |_| {
+        seen += 1;
+        seen <= statements
+    }).unwrap();
+    assert!(!finished);
+    interpreter.snapshot()
+}
+
+#[test]
+fn fingerprint_ignores_the_file_path() {
+    assert_eq!(fingerprint(&parse(SOURCE, "snap.mpl")), fingerprint(&parse(SOURCE, "./snap.mpl")));
+    assert_ne!(fingerprint(&parse(SOURCE, "snap.mpl")), fingerprint(&parse(&SOURCE.replace("0..2", "0..1"), "snap.mpl")));
+}
+
+#[test]
+fn restore_continues_under_another_path() {
+    let snapshot = snapshot_after(4);
+    let (mut interpreter, output) = Interpreter::with_buffers(parse(SOURCE, "./snap.mpl"), "");
+    interpreter.restore(snapshot).unwrap();
+    interpreter.interpret().unwrap();
+    assert_eq!(output.contents(), "2");
+}
+
+#[test]
+fn restore_rejects_variables_that_do_not_match_the_declarations() {
+    let mut snapshot = snapshot_after(4);
+    let a = snapshot.variables.iter_mut().find(|t| t.0 == "a").unwrap();
+    *a = ("a".to_string(), VariableType::String, Value::String("hello".to_string()));
+    let (mut interpreter, _) = Interpreter::with_buffers(parse(SOURCE, "snap.mpl"), "");
+    assert_eq!(interpreter.restore(snapshot).unwrap_err(), "ERROR the snapshot gives a type string, the program declares array[3] of int");
+
+    let mut snapshot = snapshot_after(4);
+    let a = snapshot.variables.iter_mut().find(|t| t.0 == "a").unwrap();
+    a.2 = Value::Array(vec![Value::Int(1)]);
+    let (mut interpreter, _) = Interpreter::with_buffers(parse(SOURCE, "snap.mpl"), "");
+    assert_eq!(interpreter.restore(snapshot).unwrap_err(), "ERROR the snapshot value of a is not of type array[3] of int");
+
+    let mut snapshot = snapshot_after(4);
+    snapshot.variables.push(("b".to_string(), VariableType::Int, Value::Int(1)));
+    let (mut interpreter, _) = Interpreter::with_buffers(parse(SOURCE, "snap.mpl"), "");
+    assert_eq!(interpreter.restore(snapshot).unwrap_err(), "ERROR the snapshot has variable b, which the program does not declare");
+}
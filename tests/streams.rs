@@ -0,0 +1,76 @@
+use minipl_interpreter::checker::type_checker::TypeChecker;
+use minipl_interpreter::data_structures::tree::ArenaTree;
+use minipl_interpreter::interpreter::interpreter::Interpreter;
+use minipl_interpreter::interpreter::runtime_error::RuntimeErrorKind;
+use minipl_interpreter::interpreter::streams::OutputBuffer;
+use minipl_interpreter::interpreter::value::Value;
+use minipl_interpreter::language::ast::{AstItem, VariableType};
+use minipl_interpreter::parser::syntax_parser::SyntaxParser;
+use minipl_interpreter::scanner::lexical_scanner::Scanner;
+
+fn parse(source: &str) -> ArenaTree<AstItem> {
+    let tokens = Scanner::new(source.to_string(), "test.mpl").scan().unwrap();
+    let ast = SyntaxParser::new(tokens).parse().unwrap();
+    TypeChecker::new(&ast).check().unwrap();
+    ast
+}
+
+fn run(source: &str, input: &str) -> (Interpreter, OutputBuffer) {
+    let (mut interpreter, output) = Interpreter::with_buffers(parse(source), input);
+    interpreter.interpret().unwrap();
+    (interpreter, output)
+}
+
+#[test]
+fn print_writes_to_the_output_buffer() {
+    let source = "var i : int;\nfor i in 1..3 do\n    print i;\n    print \" \";\nend for;\nprintln \"done\";\n";
+    let (_, output) = run(source, "");
+    assert_eq!(output.contents(), "1 2 3 done\n");
+}
+
+#[test]
+fn read_takes_whitespace_delimited_values() {
+    let source = "var n : int;\nvar s : string;\nvar b : bool;\nread n;\nread s;\nread b;\nprint n + 1, s, b;\n";
+    let (interpreter, output) = run(source, "  41 word\n\ntrue\n");
+    assert_eq!(output.contents(), "42wordtrue");
+    assert_eq!(interpreter.variables()["s"].value, Value::String("word".to_string()));
+}
+
+#[test]
+fn read_into_array_elements() {
+    let source = "var a : array[3] of int;\nvar i : int;\nfor i in 0..2 do\n    read a[i];\nend for;\nprint (a[0] + a[1]) + a[2];\n";
+    let (_, output) = run(source, "1 2\n3");
+    assert_eq!(output.contents(), "6");
+}
+
+#[test]
+fn read_past_the_end_of_input_fails() {
+    let (mut interpreter, output) = Interpreter::with_buffers(parse("var n : int;\nprint \"?\";\nread n;\n"), "");
+    let error = interpreter.interpret().unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::EndOfInput(VariableType::Int));
+    assert_eq!(error.source_info.line, 3);
+    assert_eq!(output.contents(), "?");
+}
+
+#[test]
+fn read_rejects_invalid_input() {
+    let (mut interpreter, _) = Interpreter::with_buffers(parse("var b : bool;\nread b;\n"), "yes\n");
+    let error = interpreter.interpret().unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::InvalidInput { input: "yes".to_string(), expected: VariableType::Bool });
+}
+
+#[test]
+fn output_before_a_runtime_error_is_kept() {
+    let (mut interpreter, output) = Interpreter::with_buffers(parse("print \"before\";\nprint 1 / 0;\nprint \"after\";\n"), "");
+    let error = interpreter.interpret().unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::DivisionByZero);
+    assert_eq!(output.contents(), "before");
+}
+
+#[test]
+fn output_buffer_clones_share_their_contents() {
+    let buffer = OutputBuffer::new();
+    let mut writer = buffer.clone();
+    std::io::Write::write_all(&mut writer, "shared".as_bytes()).unwrap();
+    assert_eq!(buffer.contents(), "shared");
+}
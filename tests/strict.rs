@@ -0,0 +1,46 @@
+use std::io::Cursor;
+
+use minipl_interpreter::interpreter::streams::OutputBuffer;
+use minipl_interpreter::{Engine, Error};
+
+fn check(source: &str) -> Result<String, String> {
+    let output = OutputBuffer::new();
+    let result = Engine::new()
+        .with_io(Box::new(Cursor::new(b"7".to_vec())), Box::new(output.clone()))
+        .with_strict(true)
+        .run(source);
+    match result {
+        Ok(_) => Ok(output.contents()),
+        Err(Error::Type(e)) => Err(e),
+        Err(e) => panic!("expected a strict mode error, found {:?}", e)
+    }
+}
+
+#[test]
+fn use_before_assignment_is_reported() {
+    assert_eq!(check("var x : int;\nprint x;\n"), Err("ERROR variable x may be read before it is assigned, <source>, line 2, column 7".to_string()));
+    assert_eq!(check("var x : int;\nx := 2;\nprint x;\n"), Ok("2".to_string()));
+    assert_eq!(check("var x : int := 3;\nprint x;\n"), Ok("3".to_string()));
+}
+
+#[test]
+fn assignment_inside_a_loop_body_does_not_count_after_the_loop() {
+    let source = "var i : int;\nvar x : int;\nfor i in 1..3 do\n    x := i;\nend for;\nprint x;\n";
+    assert_eq!(check(source), Err("ERROR variable x may be read before it is assigned, <source>, line 6, column 7".to_string()));
+    // The body reads x before the iteration that would have assigned it
+    let source = "var i : int;\nvar x : int;\nvar y : int := 0;\nfor i in 1..3 do\n    y := x;\n    x := i;\nend for;\n";
+    assert_eq!(check(source), Err("ERROR variable x may be read before it is assigned, <source>, line 5, column 10".to_string()));
+    let source = "var i : int;\nvar x : int;\nfor i in 1..3 do\n    x := i;\n    print x;\nend for;\n";
+    assert_eq!(check(source), Ok("123".to_string()));
+}
+
+#[test]
+fn loop_control_variable_is_assigned() {
+    let source = "var i : int;\nfor i in 1..3 do\n    print i;\nend for;\nprint i;\n";
+    assert_eq!(check(source), Ok("1234".to_string()));
+}
+
+#[test]
+fn read_counts_as_an_assignment() {
+    assert_eq!(check("var n : int;\nread n;\nprint n;\n"), Ok("7".to_string()));
+}